@@ -0,0 +1,591 @@
+//! Catalogs of localized messages, which are templates keyed by message ID and locale.
+//!
+//! Messages are loaded from files named after their locale, such as `de.ftl` or `pt-BR.toml`,
+//! in one of these formats:
+//!
+//! | Feature | Extension | Format |
+//! |---------|-----------|--------|
+//! | | `ftl` | `id = message` lines, like [Fluent](https://projectfluent.org) |
+//! | `json` | `json` | An object of messages |
+//! | `toml` | `toml` | A table of messages |
+//!
+//! In the `ftl` format, lines starting with `#` are comments, and indented lines continue the
+//! message before them on a new line. In JSON and TOML, nested tables are flattened into dotted IDs,
+//! so `errors.not_found` is the message `not_found` in the table `errors`.
+//!
+//! Every message is parsed into a [`Template`](Template) with the [`Locale`](Locale) of its tag
+//! when it is loaded, so malformed messages are reported by the load rather than when they are
+//! rendered. A load either adds every message, or reports the errors of every malformed message
+//! and file together (see [`CatalogError`](CatalogError)) and leaves the catalog unchanged.
+//!
+//! A message missing from a locale is looked up in its fallback locales: those set with
+//! [`Catalog::with_fallback`](Catalog::with_fallback), then its parent tags (`pt` for `pt-BR`),
+//! and then the default locale, set with [`Catalog::with_default_locale`](Catalog::with_default_locale).
+//!
+//! # Example
+//!
+//! ```rust
+//! use curly::catalog::{Catalog, Format};
+//!
+//! let mut catalog = Catalog::new().with_default_locale("en");
+//! catalog
+//!     .load_str("en", Format::Messages, "greeting = Hello, {0}!\nfarewell = Bye!")
+//!     .unwrap();
+//! catalog
+//!     .load_str("de", Format::Messages, "greeting = Hallo, {0}!")
+//!     .unwrap();
+//!
+//! let values: &[&dyn curly::formatting::CurlyFmt] = &[&"Welt"];
+//! assert_eq!(catalog.render("greeting", "de-AT", &values).unwrap(), "Hallo, Welt!");
+//! assert_eq!(catalog.render("farewell", "de-AT", &values).unwrap(), "Bye!");
+//! ```
+
+use crate::locale::Locale;
+use crate::prelude::*;
+use crate::{Provider, Template};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// A format of message files.
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash)]
+pub enum Format {
+    /// `id = message` lines, with `#` comments and indented continuation lines (`.ftl`).
+    Messages,
+    /// A JSON object of messages (`.json`).
+    #[cfg(feature = "json")]
+    Json,
+    /// A TOML table of messages (`.toml`).
+    #[cfg(feature = "toml")]
+    Toml,
+}
+
+impl Format {
+    /// The format of files with the extension `extension`, or `None` if it isn't supported.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "ftl" => Some(Format::Messages),
+            #[cfg(feature = "json")]
+            "json" => Some(Format::Json),
+            #[cfg(feature = "toml")]
+            "toml" => Some(Format::Toml),
+            _ => None,
+        }
+    }
+}
+
+/// A catalog of localized messages (see the [module documentation](self)).
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    messages: HashMap<String, HashMap<String, Template>>,
+    fallbacks: HashMap<String, Vec<String>>,
+    default_locale: Option<String>,
+}
+
+impl Catalog {
+    /// Create an empty catalog without a default locale.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Look up messages missing from every other locale in `locale`.
+    #[must_use]
+    pub fn with_default_locale(mut self, locale: &str) -> Self {
+        self.default_locale = Some(normalize(locale));
+        self
+    }
+
+    /// Look up messages missing from `locale` in `fallback`, before the parent tags of `locale`.
+    ///
+    /// Fallbacks are looked up in the order they are added, along with their own fallbacks.
+    #[must_use]
+    pub fn with_fallback(mut self, locale: &str, fallback: &str) -> Self {
+        self.fallbacks
+            .entry(normalize(locale))
+            .or_default()
+            .push(normalize(fallback));
+        self
+    }
+
+    /// Add the messages in `messages`, as pairs of IDs and format strings, to `locale`.
+    ///
+    /// # Errors
+    ///
+    /// A [`CatalogError`](CatalogError) with the errors of every malformed message.
+    pub fn add_messages<I, S>(&mut self, locale: &str, messages: I) -> CurlyResult<()>
+    where
+        I: IntoIterator<Item = (S, S)>,
+        S: AsRef<str>,
+    {
+        let mut loader = Loader::default();
+        for (id, message) in messages {
+            loader.add(locale, locale, id.as_ref(), message.as_ref());
+        }
+        loader.finish(self)
+    }
+
+    /// Add the messages in `source`, written in `format`, to `locale`.
+    ///
+    /// # Errors
+    ///
+    /// A [`CatalogError`](CatalogError) with the errors of every malformed message, or the
+    /// error if `source` isn't valid in `format`.
+    pub fn load_str(&mut self, locale: &str, format: Format, source: &str) -> CurlyResult<()> {
+        let mut loader = Loader::default();
+        loader.load_str(locale, locale, format, source);
+        loader.finish(self)
+    }
+
+    /// Add the messages in the file at `path` to the locale named by its stem, e.g. `de` for
+    /// `locales/de.ftl`, in the format of its extension.
+    ///
+    /// # Errors
+    ///
+    /// A [`CatalogError`](CatalogError) with the errors of every malformed message, or the error
+    /// if the file can't be read or isn't valid in its format.
+    pub fn load_file(&mut self, path: impl AsRef<Path>) -> CurlyResult<()> {
+        let mut loader = Loader::default();
+        loader.load_file(path.as_ref());
+        loader.finish(self)
+    }
+
+    /// Add the messages in every file in the directory at `path` with a supported extension,
+    /// like [`load_file`](Catalog::load_file).
+    ///
+    /// # Errors
+    ///
+    /// A [`CatalogError`](CatalogError) with the errors of every file and malformed message.
+    pub fn load_dir(&mut self, path: impl AsRef<Path>) -> CurlyResult<()> {
+        let path = path.as_ref();
+        let mut loader = Loader::default();
+        match std::fs::read_dir(path).and_then(Iterator::collect::<std::io::Result<Vec<_>>>) {
+            Ok(entries) => {
+                let mut paths: Vec<_> = entries.into_iter().map(|entry| entry.path()).collect();
+                paths.sort();
+                for path in paths {
+                    let extension = path.extension().and_then(|extension| extension.to_str());
+                    if path.is_file() && extension.and_then(Format::from_extension).is_some() {
+                        loader.load_file(&path);
+                    }
+                }
+            }
+            Err(error) => loader.error(&path.display().to_string(), None, Box::new(error)),
+        }
+        loader.finish(self)
+    }
+
+    /// The message `id` in `locale`, or in the first of its fallback locales that has it.
+    pub fn get(&self, id: &str, locale: &str) -> Option<&Template> {
+        self.fallback_chain(&normalize(locale))
+            .into_iter()
+            .find_map(|locale| self.messages.get(locale)?.get(id))
+    }
+
+    /// Render the message `id` in `locale` using the values provided by `provider`.
+    ///
+    /// # Errors
+    ///
+    /// [`CurlyErrorKind::Generic`](CurlyErrorKind::Generic) if neither `locale` nor its fallback
+    /// locales have the message, or any error returned while rendering it.
+    pub fn render(&self, id: &str, locale: &str, provider: &dyn Provider) -> CurlyFmtResult {
+        match self.get(id, locale) {
+            Some(template) => template.render(provider),
+            None => Err(CurlyErrorKind::Generic(CurlyError::from_boxed(format!(
+                "No message `{id}` for the locale `{locale}` or its fallbacks"
+            )))),
+        }
+    }
+
+    /// The locales of `locale` and its fallbacks, in the order messages are looked up in them.
+    fn fallback_chain<'a>(&'a self, locale: &'a str) -> Vec<&'a str> {
+        let mut chain = Vec::new();
+        self.push_fallbacks(locale, &mut chain);
+        if let Some(default_locale) = &self.default_locale {
+            self.push_fallbacks(default_locale, &mut chain);
+        }
+        chain
+    }
+
+    fn push_fallbacks<'a>(&'a self, locale: &'a str, chain: &mut Vec<&'a str>) {
+        if chain.contains(&locale) {
+            return;
+        }
+        chain.push(locale);
+        for fallback in self.fallbacks.get(locale).into_iter().flatten() {
+            self.push_fallbacks(fallback, chain);
+        }
+        if let Some((parent, _)) = locale.rsplit_once('-') {
+            self.push_fallbacks(parent, chain);
+        }
+    }
+}
+
+/// Normalize a locale tag such as `pt_BR.UTF-8` to `pt-BR`.
+fn normalize(locale: &str) -> String {
+    let tag = locale.split('.').next().unwrap_or_default();
+    tag.replace('_', "-")
+}
+
+/// The errors of a load into a [`Catalog`](Catalog), for every malformed message and every file
+/// that couldn't be read.
+///
+/// It is returned as a [`CurlyErrorKind::Generic`](CurlyErrorKind::Generic), and can be
+/// downcast to get the individual errors.
+///
+/// # Example
+///
+/// ```rust
+/// use curly::catalog::{Catalog, CatalogError, Format};
+/// use curly::CurlyErrorKind;
+///
+/// let mut catalog = Catalog::new();
+/// let error = catalog
+///     .load_str("en", Format::Messages, "a = {\nb = Hi\nc = {n, plural, one {#}}")
+///     .unwrap_err();
+/// let CurlyErrorKind::Generic(error) = error else { unreachable!() };
+/// let error = error.downcast_ref::<CatalogError>().unwrap();
+/// let ids: Vec<_> = error.errors().map(|(_, id, _)| id).collect();
+/// assert_eq!(ids, [Some("a"), Some("c")]);
+/// assert!(catalog.get("b", "en").is_none());
+/// ```
+#[derive(Debug)]
+pub struct CatalogError {
+    errors: Vec<(String, Option<String>, CurlyErrorKind)>,
+}
+
+impl CatalogError {
+    /// The errors, with the locale or file they occurred in, and the ID of their message unless
+    /// the whole file couldn't be loaded.
+    pub fn errors(&self) -> impl Iterator<Item = (&str, Option<&str>, &CurlyErrorKind)> {
+        self.errors
+            .iter()
+            .map(|(origin, id, error)| (origin.as_str(), id.as_deref(), error))
+    }
+}
+
+impl std::fmt::Display for CatalogError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            fmt,
+            "Failed to load the catalog ({} errors)",
+            self.errors.len()
+        )?;
+        for (origin, id, error) in &self.errors {
+            match id {
+                Some(id) => write!(fmt, "\n{origin}: `{id}`: {error}")?,
+                None => write!(fmt, "\n{origin}: {error}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for CatalogError {}
+
+/// The messages and errors of a load, which are added to a catalog once every file is loaded.
+#[derive(Default)]
+struct Loader {
+    messages: Vec<(String, String, Template)>,
+    /// The locale and ID of every message, to find duplicates.
+    ids: HashSet<(String, String)>,
+    errors: Vec<(String, Option<String>, CurlyErrorKind)>,
+}
+
+impl Loader {
+    fn add(&mut self, origin: &str, locale: &str, id: &str, message: &str) {
+        let locale = normalize(locale);
+        if !self.ids.insert((locale.clone(), id.to_string())) {
+            let error = CurlyError::from_boxed(format!("Duplicate message `{id}`"));
+            self.error(origin, Some(id), error);
+            return;
+        }
+
+        match Template::parse(message) {
+            Ok(template) => {
+                let template = template.with_locale(Locale::from_tag(&locale).unwrap_or_default());
+                self.messages.push((locale, id.to_string(), template));
+            }
+            Err(error) => self
+                .errors
+                .push((origin.to_string(), Some(id.to_string()), error)),
+        }
+    }
+
+    fn error(&mut self, origin: &str, id: Option<&str>, error: Box<dyn std::error::Error>) {
+        self.errors.push((
+            origin.to_string(),
+            id.map(str::to_string),
+            CurlyErrorKind::Generic(error),
+        ));
+    }
+
+    fn load_file(&mut self, path: &Path) {
+        let origin = path.display().to_string();
+        let locale = path.file_stem().and_then(|stem| stem.to_str());
+        let extension = path.extension().and_then(|extension| extension.to_str());
+        let Some(format) = extension.and_then(Format::from_extension) else {
+            let error = format!(
+                "Unsupported message file extension `{}`",
+                extension.unwrap_or("")
+            );
+            return self.error(&origin, None, CurlyError::from_boxed(error));
+        };
+        let Some(locale) = locale else {
+            let error = String::from("Expected a locale as the file name");
+            return self.error(&origin, None, CurlyError::from_boxed(error));
+        };
+
+        match std::fs::read_to_string(path) {
+            Ok(source) => self.load_str(&origin, locale, format, &source),
+            Err(error) => self.error(&origin, None, Box::new(error)),
+        }
+    }
+
+    fn load_str(&mut self, origin: &str, locale: &str, format: Format, source: &str) {
+        match format {
+            Format::Messages => self.load_messages(origin, locale, source),
+            #[cfg(feature = "json")]
+            Format::Json => match serde_json::from_str(source) {
+                Ok(serde_json::Value::Object(entries)) => {
+                    self.load_json(origin, locale, "", &entries);
+                }
+                Ok(_) => self.error(origin, None, expected_table()),
+                Err(error) => self.error(origin, None, Box::new(error)),
+            },
+            #[cfg(feature = "toml")]
+            Format::Toml => match toml::from_str(source) {
+                Ok(entries) => self.load_toml(origin, locale, "", &entries),
+                Err(error) => self.error(origin, None, Box::new(error)),
+            },
+        }
+    }
+
+    fn load_messages(&mut self, origin: &str, locale: &str, source: &str) {
+        let mut messages: Vec<(&str, String)> = Vec::new();
+        for (index, line) in source.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            if line.starts_with(char::is_whitespace) {
+                if let Some((_, message)) = messages.last_mut() {
+                    message.push('\n');
+                    message.push_str(trimmed);
+                    continue;
+                }
+            }
+
+            match trimmed.split_once('=') {
+                Some((id, message)) if is_message_id(id.trim()) => {
+                    messages.push((id.trim(), message.trim().to_string()));
+                }
+                _ => {
+                    let error = format!("Expected `id = message` on line {}", index + 1);
+                    self.error(origin, None, CurlyError::from_boxed(error));
+                }
+            }
+        }
+
+        for (id, message) in messages {
+            self.add(origin, locale, id, &message);
+        }
+    }
+
+    #[cfg(feature = "json")]
+    fn load_json(
+        &mut self,
+        origin: &str,
+        locale: &str,
+        prefix: &str,
+        entries: &serde_json::Map<String, serde_json::Value>,
+    ) {
+        for (key, value) in entries {
+            let id = join_id(prefix, key);
+            match value {
+                serde_json::Value::String(message) => self.add(origin, locale, &id, message),
+                serde_json::Value::Object(entries) => self.load_json(origin, locale, &id, entries),
+                _ => self.error(origin, Some(&id), expected_message()),
+            }
+        }
+    }
+
+    #[cfg(feature = "toml")]
+    fn load_toml(&mut self, origin: &str, locale: &str, prefix: &str, entries: &toml::Table) {
+        for (key, value) in entries {
+            let id = join_id(prefix, key);
+            match value {
+                toml::Value::String(message) => self.add(origin, locale, &id, message),
+                toml::Value::Table(entries) => self.load_toml(origin, locale, &id, entries),
+                _ => self.error(origin, Some(&id), expected_message()),
+            }
+        }
+    }
+
+    fn finish(self, catalog: &mut Catalog) -> CurlyResult<()> {
+        if !self.errors.is_empty() {
+            return Err(CurlyErrorKind::Generic(Box::new(CatalogError {
+                errors: self.errors,
+            })));
+        }
+        for (locale, id, template) in self.messages {
+            catalog
+                .messages
+                .entry(locale)
+                .or_default()
+                .insert(id, template);
+        }
+        Ok(())
+    }
+}
+
+fn is_message_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|ch| ch.is_alphanumeric() || matches!(ch, '_' | '-' | '.'))
+}
+
+#[cfg(any(feature = "json", feature = "toml"))]
+fn join_id(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{prefix}.{key}")
+    }
+}
+
+#[cfg(any(feature = "json", feature = "toml"))]
+fn expected_message() -> Box<dyn std::error::Error> {
+    CurlyError::from_boxed(String::from("Expected a message or a table of messages"))
+}
+
+#[cfg(feature = "json")]
+fn expected_table() -> Box<dyn std::error::Error> {
+    CurlyError::from_boxed(String::from("Expected an object of messages"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(catalog: &Catalog, id: &str, locale: &str) -> CurlyFmtResult {
+        let values: &[&dyn CurlyFmt] = &[&1_234.5];
+        catalog.render(id, locale, &values)
+    }
+
+    #[test]
+    fn fallbacks() {
+        let mut catalog = Catalog::new()
+            .with_default_locale("en")
+            .with_fallback("pt-BR", "pt-PT");
+        catalog
+            .add_messages("en", [("a", "en {0:,}"), ("b", "en b"), ("c", "en c")])
+            .unwrap();
+        catalog
+            .add_messages("pt-PT", [("a", "pt-PT {0:,}")])
+            .unwrap();
+        catalog.add_messages("pt", [("b", "pt b")]).unwrap();
+
+        assert_eq!(render(&catalog, "a", "en").unwrap(), "en 1,234.5");
+        assert_eq!(
+            render(&catalog, "a", "pt_BR.UTF-8").unwrap(),
+            "pt-PT 1\u{a0}234,5"
+        );
+        assert_eq!(render(&catalog, "b", "pt-BR").unwrap(), "pt b");
+        assert_eq!(render(&catalog, "c", "pt-BR").unwrap(), "en c");
+        assert_eq!(render(&catalog, "a", "fr").unwrap(), "en 1,234.5");
+        assert_eq!(
+            render(&catalog, "d", "fr").unwrap_err().to_string(),
+            "Error: No message `d` for the locale `fr` or its fallbacks"
+        );
+
+        let catalog = Catalog::new()
+            .with_fallback("a", "b")
+            .with_fallback("b", "a");
+        assert_eq!(catalog.fallback_chain("a-x"), ["a-x", "a", "b"]);
+    }
+
+    #[test]
+    fn messages_format() {
+        let source = "\
+# Comments and blank lines are ignored
+
+files = {0, plural,
+    one {# file}
+    other {# files}}
+nested.id = Value:
+  {0}
+";
+        let mut catalog = Catalog::new();
+        catalog.load_str("de", Format::Messages, source).unwrap();
+        assert_eq!(render(&catalog, "files", "de").unwrap(), "1234,5 files");
+        assert_eq!(
+            render(&catalog, "nested.id", "de").unwrap(),
+            "Value:\n1234,5"
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let mut catalog = Catalog::new();
+        let source = "a = {\n  continued\nnot a message\nb = ok\nb = again";
+        let error = catalog
+            .load_str("en", Format::Messages, source)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            error.lines().next(),
+            Some("Error: Failed to load the catalog (3 errors)")
+        );
+        assert_eq!(
+            error.lines().nth(1),
+            Some("en: Error: Expected `id = message` on line 3")
+        );
+        assert!(error
+            .lines()
+            .nth(2)
+            .unwrap()
+            .starts_with("en: `a`: Syntax Error: "));
+        assert_eq!(
+            error.lines().nth(3),
+            Some("en: `b`: Error: Duplicate message `b`")
+        );
+        assert!(catalog.get("b", "en").is_none());
+
+        let error = catalog.load_file("locales/en.txt").unwrap_err().to_string();
+        assert!(error.ends_with("locales/en.txt: Error: Unsupported message file extension `txt`"));
+        assert!(catalog.load_dir("does-not-exist").is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json() {
+        let mut catalog = Catalog::new();
+        let source = r#"{"greeting": "Hallo {0}", "errors": {"not_found": "Fehlt: {0}"}}"#;
+        catalog.load_str("de", Format::Json, source).unwrap();
+        assert_eq!(
+            render(&catalog, "errors.not_found", "de").unwrap(),
+            "Fehlt: 1234,5"
+        );
+
+        let error = catalog
+            .load_str("de", Format::Json, r#"{"a": 1, "b": "{"}"#)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("de: `a`: Error: Expected a message or a table of messages"));
+        assert!(error.contains("de: `b`: Syntax Error: "));
+        assert!(catalog.load_str("de", Format::Json, "[]").is_err());
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml() {
+        let mut catalog = Catalog::new();
+        let source = "greeting = \"Hi {0}\"\n[errors]\nnot_found = \"Missing: {0}\"";
+        catalog.load_str("en", Format::Toml, source).unwrap();
+        assert_eq!(
+            render(&catalog, "errors.not_found", "en").unwrap(),
+            "Missing: 1234.5"
+        );
+        assert!(catalog.load_str("en", Format::Toml, "a = ").is_err());
+    }
+}
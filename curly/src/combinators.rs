@@ -0,0 +1,385 @@
+//! Providers that combine or adapt other providers, created by the combinator methods of
+//! [`Provider`](Provider), such as [`or`](Provider::or) and [`with`](Provider::with).
+//!
+//! # Example
+//!
+//! ```rust
+//! use curly::Provider;
+//! use curly::Template;
+//! use std::collections::HashMap;
+//!
+//! let mut global = HashMap::new();
+//! global.insert("site", String::from("Example"));
+//! global.insert("theme", String::from("light"));
+//! let mut tenant = HashMap::new();
+//! tenant.insert("theme", String::from("dark"));
+//!
+//! let provider = tenant.or(global).with("user", String::from("ann"));
+//! let template = Template::parse("{site} ({theme}) for {user}").unwrap();
+//! assert_eq!(template.render(&provider).unwrap(), "Example (dark) for ann");
+//! ```
+
+use crate::prelude::*;
+use crate::{Provider, ProviderIter};
+
+fn invalid_key(key: &str) -> CurlyErrorKind {
+    CurlyErrorKind::Generic(CurlyError::from_boxed(format!(
+        "Invalid format specifier `{key}`"
+    )))
+}
+
+/// Provides keys from `first`, falling back to `second` for keys that `first` doesn't
+/// contain. Created by [`Provider::or`](Provider::or).
+#[derive(Debug, Clone)]
+pub struct Or<A, B> {
+    pub(crate) first: A,
+    pub(crate) second: B,
+}
+
+impl<A: Provider, B: Provider> Or<A, B> {
+    fn select(&self, key: &str) -> &dyn Provider {
+        if self.first.contains_key(key) {
+            &self.first
+        } else {
+            &self.second
+        }
+    }
+}
+
+impl<A: Provider, B: Provider> Provider for Or<A, B> {
+    fn provide(&self, context: &CurlyContext, key: &str) -> CurlyFmtResult {
+        self.select(key).provide(context, key)
+    }
+
+    fn provide_to(
+        &self,
+        context: &CurlyContext,
+        key: &str,
+        out: &mut dyn std::fmt::Write,
+    ) -> CurlyResult<()> {
+        self.select(key).provide_to(context, key, out)
+    }
+
+    fn is_truthy(&self, key: &str) -> CurlyResult<bool> {
+        self.select(key).is_truthy(key)
+    }
+
+    fn provide_each(&self, key: &str) -> CurlyResult<ProviderIter<'_>> {
+        self.select(key).provide_each(key)
+    }
+
+    fn contains_key(&self, key: &str) -> bool {
+        self.first.contains_key(key) || self.second.contains_key(key)
+    }
+
+    fn is_safe(&self, key: &str) -> bool {
+        self.select(key).is_safe(key)
+    }
+}
+
+/// Provides `value` as `key`, and every other key from `inner`.
+/// Created by [`Provider::with`](Provider::with).
+#[derive(Debug, Clone)]
+pub struct With<P, V> {
+    pub(crate) inner: P,
+    pub(crate) key: String,
+    pub(crate) value: V,
+}
+
+impl<P: Provider, V: CurlyFmt> Provider for With<P, V> {
+    fn provide(&self, context: &CurlyContext, key: &str) -> CurlyFmtResult {
+        if key == self.key {
+            self.value.curly_fmt(context)
+        } else {
+            self.inner.provide(context, key)
+        }
+    }
+
+    fn provide_to(
+        &self,
+        context: &CurlyContext,
+        key: &str,
+        out: &mut dyn std::fmt::Write,
+    ) -> CurlyResult<()> {
+        if key == self.key {
+            self.value.curly_write(context, out)
+        } else {
+            self.inner.provide_to(context, key, out)
+        }
+    }
+
+    fn is_truthy(&self, key: &str) -> CurlyResult<bool> {
+        if key == self.key {
+            self.value.curly_is_truthy()
+        } else {
+            self.inner.is_truthy(key)
+        }
+    }
+
+    fn provide_each(&self, key: &str) -> CurlyResult<ProviderIter<'_>> {
+        if key == self.key {
+            return Err(CurlyErrorKind::Generic(CurlyError::from_boxed(format!(
+                "Cannot iterate over `{key}`"
+            ))));
+        }
+        self.inner.provide_each(key)
+    }
+
+    fn contains_key(&self, key: &str) -> bool {
+        key == self.key || self.inner.contains_key(key)
+    }
+
+    fn is_safe(&self, key: &str) -> bool {
+        if key == self.key {
+            self.value.is_safe()
+        } else {
+            self.inner.is_safe(key)
+        }
+    }
+}
+
+/// Provides the keys of `inner` under a prefix, e.g. `{user.name}` for `name` with the prefix `user.`.
+/// Created by [`Provider::prefixed`](Provider::prefixed).
+#[derive(Debug, Clone)]
+pub struct Prefixed<P> {
+    pub(crate) inner: P,
+    pub(crate) prefix: String,
+}
+
+impl<P: Provider> Prefixed<P> {
+    fn strip<'k>(&self, key: &'k str) -> CurlyResult<&'k str> {
+        key.strip_prefix(self.prefix.as_str())
+            .ok_or_else(|| invalid_key(key))
+    }
+}
+
+impl<P: Provider> Provider for Prefixed<P> {
+    fn provide(&self, context: &CurlyContext, key: &str) -> CurlyFmtResult {
+        self.inner.provide(context, self.strip(key)?)
+    }
+
+    fn provide_to(
+        &self,
+        context: &CurlyContext,
+        key: &str,
+        out: &mut dyn std::fmt::Write,
+    ) -> CurlyResult<()> {
+        self.inner.provide_to(context, self.strip(key)?, out)
+    }
+
+    fn is_truthy(&self, key: &str) -> CurlyResult<bool> {
+        self.inner.is_truthy(self.strip(key)?)
+    }
+
+    fn provide_each(&self, key: &str) -> CurlyResult<ProviderIter<'_>> {
+        self.inner.provide_each(self.strip(key)?)
+    }
+
+    fn contains_key(&self, key: &str) -> bool {
+        self.strip(key)
+            .is_ok_and(|key| self.inner.contains_key(key))
+    }
+
+    fn is_safe(&self, key: &str) -> bool {
+        self.strip(key).is_ok_and(|key| self.inner.is_safe(key))
+    }
+}
+
+/// Provides only the allowed keys of `inner`. Created by [`Provider::only`](Provider::only).
+#[derive(Debug, Clone)]
+pub struct Only<P> {
+    pub(crate) inner: P,
+    pub(crate) keys: Vec<String>,
+}
+
+impl<P: Provider> Only<P> {
+    fn allow(&self, key: &str) -> CurlyResult<()> {
+        if self.keys.iter().any(|allowed| allowed == key) {
+            Ok(())
+        } else {
+            Err(invalid_key(key))
+        }
+    }
+}
+
+impl<P: Provider> Provider for Only<P> {
+    fn provide(&self, context: &CurlyContext, key: &str) -> CurlyFmtResult {
+        self.allow(key)?;
+        self.inner.provide(context, key)
+    }
+
+    fn provide_to(
+        &self,
+        context: &CurlyContext,
+        key: &str,
+        out: &mut dyn std::fmt::Write,
+    ) -> CurlyResult<()> {
+        self.allow(key)?;
+        self.inner.provide_to(context, key, out)
+    }
+
+    fn is_truthy(&self, key: &str) -> CurlyResult<bool> {
+        self.allow(key)?;
+        self.inner.is_truthy(key)
+    }
+
+    fn provide_each(&self, key: &str) -> CurlyResult<ProviderIter<'_>> {
+        self.allow(key)?;
+        self.inner.provide_each(key)
+    }
+
+    fn contains_key(&self, key: &str) -> bool {
+        self.allow(key).is_ok() && self.inner.contains_key(key)
+    }
+
+    fn is_safe(&self, key: &str) -> bool {
+        self.allow(key).is_ok() && self.inner.is_safe(key)
+    }
+}
+
+/// Transforms the errors of `inner` with a closure. Created by [`Provider::map_err`](Provider::map_err).
+#[derive(Debug, Clone)]
+pub struct MapErr<P, F> {
+    pub(crate) inner: P,
+    pub(crate) map: F,
+}
+
+impl<P, F> Provider for MapErr<P, F>
+where
+    P: Provider,
+    F: Fn(CurlyErrorKind) -> CurlyErrorKind,
+{
+    fn provide(&self, context: &CurlyContext, key: &str) -> CurlyFmtResult {
+        self.inner.provide(context, key).map_err(&self.map)
+    }
+
+    fn provide_to(
+        &self,
+        context: &CurlyContext,
+        key: &str,
+        out: &mut dyn std::fmt::Write,
+    ) -> CurlyResult<()> {
+        self.inner.provide_to(context, key, out).map_err(&self.map)
+    }
+
+    fn is_truthy(&self, key: &str) -> CurlyResult<bool> {
+        self.inner.is_truthy(key).map_err(&self.map)
+    }
+
+    fn provide_each(&self, key: &str) -> CurlyResult<ProviderIter<'_>> {
+        self.inner.provide_each(key).map_err(&self.map)
+    }
+
+    fn contains_key(&self, key: &str) -> bool {
+        self.inner.contains_key(key)
+    }
+
+    fn is_safe(&self, key: &str) -> bool {
+        self.inner.is_safe(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::invalid_key;
+    use crate::prelude::*;
+    use crate::{Provider, Template};
+    use std::collections::BTreeMap;
+
+    fn map(entries: &[(&'static str, &str)]) -> BTreeMap<&'static str, String> {
+        entries
+            .iter()
+            .map(|&(key, value)| (key, value.to_string()))
+            .collect()
+    }
+
+    fn render(format_string: &str, provider: &dyn Provider) -> Result<String, String> {
+        Template::parse(format_string)
+            .unwrap()
+            .render(provider)
+            .map_err(|error| error.to_string())
+    }
+
+    #[test]
+    fn layering() {
+        let global = map(&[("site", "Example"), ("theme", "light"), ("lang", "en")]);
+        let tenant = map(&[("theme", "dark")]);
+        let request = map(&[("name", "Ann"), ("secret", "hunter2")]);
+
+        let provider = request
+            .only(&["name"])
+            .prefixed("user.")
+            .or(tenant.or(global))
+            .with("lang", String::from("fr"));
+
+        assert_eq!(
+            render("{site}/{theme}/{lang}: {user.name:>4}", &provider).unwrap(),
+            "Example/dark/fr:  Ann"
+        );
+        assert!(render("{user.secret}", &provider).is_err());
+        assert!(render("{name}", &provider).is_err());
+        assert!(provider.contains_key("user.name"));
+        assert!(!provider.contains_key("user.secret"));
+        assert!(provider.is_truthy("lang").unwrap());
+    }
+
+    #[test]
+    fn truthiness() {
+        let provider = map(&[("name", "Ann")]).with("admin", false).with("n", 0u8);
+        let format_string = "{#if admin}admin{#else}user{/if} {#if n}{n}{#else}none{/if}";
+        assert_eq!(render(format_string, &provider).unwrap(), "user none");
+        let provider = provider.with("admin", true).with("n", 2u8);
+        assert_eq!(render(format_string, &provider).unwrap(), "admin 2");
+
+        let provider = map(&[])
+            .with("initial", 'a')
+            .with("tags", std::collections::VecDeque::<String>::new());
+        let format_string = "{#if initial}{initial}{/if}{#if tags}{tags}{#else}-{/if}";
+        assert_eq!(render(format_string, &provider).unwrap(), "a-");
+    }
+
+    #[test]
+    fn manual_fallback() {
+        struct Manual;
+
+        impl Provider for Manual {
+            fn provide(&self, context: &CurlyContext, key: &str) -> CurlyFmtResult {
+                match key {
+                    "a" => "manual".curly_fmt(context),
+                    _ => Err(invalid_key(key)),
+                }
+            }
+
+            fn contains_key(&self, key: &str) -> bool {
+                key == "a"
+            }
+        }
+
+        let provider = Manual.or(map(&[("a", "shadowed"), ("b", "mapped")]));
+        assert_eq!(render("{a} {b}", &provider).unwrap(), "manual mapped");
+        assert!(render("{c}", &provider).is_err());
+    }
+
+    #[test]
+    fn closure_fallback() {
+        let computed = crate::providers::provider_fn(|context, key| match key {
+            "a" => Some("computed".curly_fmt(context)),
+            _ => None,
+        })
+        .with_keys(|key| key == "a");
+        let provider = computed.or(map(&[("a", "shadowed"), ("b", "mapped")]));
+        assert_eq!(render("{a} {b}", &provider).unwrap(), "computed mapped");
+        assert!(render("{c}", &provider).is_err());
+    }
+
+    #[test]
+    fn map_err() {
+        let provider = map(&[]).map_err(|error| {
+            CurlyErrorKind::Generic(CurlyError::from_boxed(format!("In greeting: {error}")))
+        });
+        assert_eq!(
+            render("{name}", &provider).unwrap_err(),
+            "Error: In greeting: Error: Invalid format specifier `name`"
+        );
+    }
+}
@@ -0,0 +1,449 @@
+//! [`CurlyFmt`](CurlyFmt) implementations for dates and times.
+//!
+//! | Feature | Types |
+//! |---------|-------|
+//! | `chrono` | [`chrono::DateTime`](https://docs.rs/chrono/0.4/chrono/struct.DateTime.html), [`chrono::NaiveDate`](https://docs.rs/chrono/0.4/chrono/naive/struct.NaiveDate.html) |
+//! | `time` | [`time::OffsetDateTime`](https://docs.rs/time/0.3/time/struct.OffsetDateTime.html) |
+//!
+//! The custom flags are a strftime pattern (`{created:%Y-%m-%d %H:%M}`), or one of these presets:
+//!
+//! | Preset | Example |
+//! |--------|---------|
+//! | `rfc3339` | `2024-03-05T14:07:09+01:00`, the default for dates with a time |
+//! | `rfc2822` | `Tue, 05 Mar 2024 14:07:09 +0100` |
+//! | `timestamp` | `1709644029`, the Unix timestamp in seconds |
+//! | `relative` | `3 hours ago` or `in 2 days` |
+//!
+//! Dates without a time, such as `NaiveDate`, are formatted as `%Y-%m-%d` by default, and at
+//! midnight UTC for `timestamp` and `relative`.
+//!
+//! `relative` is relative to the current time, or to the Unix timestamp in the `@now` custom flag
+//! (`{created:relative @now=1709644029}`).
+//!
+//! # Patterns
+//!
+//! | Specifier | Example | Description |
+//! |-----------|---------|-------------|
+//! | `%Y` | `2024` | The year |
+//! | `%C`, `%y` | `20`, `24` | The century and the year within the century |
+//! | `%m` | `03` | The month |
+//! | `%b`, `%B` | `Mar`, `March` | The name of the month |
+//! | `%d`, `%e` | `05`, ` 5` | The day of the month |
+//! | `%j` | `065` | The day of the year |
+//! | `%a`, `%A` | `Tue`, `Tuesday` | The name of the day of the week |
+//! | `%u`, `%w` | `2`, `2` | The day of the week, from Monday as `1` and from Sunday as `0` |
+//! | `%H`, `%I` | `14`, `02` | The hour, on a 24 and 12 hour clock |
+//! | `%M`, `%S` | `07`, `09` | The minute and second |
+//! | `%p`, `%P` | `PM`, `pm` | AM or PM |
+//! | `%f`, `%.f` | `026490000`, `.026490` | The nanoseconds, and the fraction of a second if there is one |
+//! | `%z`, `%:z` | `+0100`, `+01:00` | The offset from UTC |
+//! | `%s` | `1709644029` | The Unix timestamp in seconds |
+//! | `%F`, `%T` | `2024-03-05`, `14:07:09` | `%Y-%m-%d` and `%H:%M:%S` |
+//! | `%D`, `%R` | `03/05/24`, `14:07` | `%m/%d/%y` and `%H:%M` |
+//! | `%%`, `%n`, `%t` | `%` | A literal `%`, newline and tab |
+//!
+//! # Example
+//!
+//! ```rust
+//! # #[cfg(feature = "chrono")] {
+//! use chrono::{TimeZone, Utc};
+//! use curly::Template;
+//!
+//! let created = Utc.with_ymd_and_hms(2024, 3, 5, 14, 7, 9).unwrap();
+//! let values: &[&dyn curly::formatting::CurlyFmt] = &[&created];
+//! let template = Template::parse("{0:%d %B %Y} ({0:relative @now=1709658429})").unwrap();
+//! assert_eq!(template.render(&values).unwrap(), "05 March 2024 (3 hours ago)");
+//! # }
+//! ```
+
+use crate::prelude::*;
+use std::convert::TryFrom;
+use std::fmt::Write;
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+const WEEKDAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+/// The fields of a date, and optionally a time and an offset from UTC.
+struct Parts {
+    year: i32,
+    /// From 1 to 12.
+    month: u32,
+    day: u32,
+    ordinal: u32,
+    /// Days from Monday.
+    weekday: u32,
+    time: Option<Time>,
+    /// Seconds east of UTC.
+    offset: Option<i32>,
+    timestamp: i64,
+}
+
+struct Time {
+    hour: u32,
+    minute: u32,
+    second: u32,
+    nanosecond: u32,
+}
+
+impl Parts {
+    fn time(&self, pattern: &str, spec: &str) -> CurlyResult<&Time> {
+        self.time.as_ref().ok_or_else(|| {
+            CurlyErrorKind::Generic(CurlyError::from_boxed(format!(
+                "`%{spec}` in the pattern `{pattern}` requires a time"
+            )))
+        })
+    }
+
+    fn offset(&self, pattern: &str, spec: &str) -> CurlyResult<i32> {
+        self.offset.ok_or_else(|| {
+            CurlyErrorKind::Generic(CurlyError::from_boxed(format!(
+                "`%{spec}` in the pattern `{pattern}` requires a time zone"
+            )))
+        })
+    }
+}
+
+/// Format `parts` according to the custom flags of `context`, and pad the result.
+fn write_parts(
+    parts: &Parts,
+    context: &CurlyContext,
+    out: &mut dyn std::fmt::Write,
+) -> CurlyResult<()> {
+    let mut formatted = String::new();
+    match context.custom_pattern() {
+        Some("rfc3339") => write_pattern(parts, "%Y-%m-%dT%H:%M:%S%.f%:z", &mut formatted)?,
+        Some("rfc2822") => write_pattern(parts, "%a, %d %b %Y %H:%M:%S %z", &mut formatted)?,
+        Some("timestamp") => write!(formatted, "{}", parts.timestamp)?,
+        Some("relative") => {
+            let now = match context.custom::<i64>("now")? {
+                Some(now) => now,
+                None => now(),
+            };
+            write_relative(parts.timestamp - now, &mut formatted)?;
+        }
+        Some(pattern) => write_pattern(parts, pattern, &mut formatted)?,
+        None if parts.time.is_some() => {
+            write_pattern(parts, "%Y-%m-%dT%H:%M:%S%.f%:z", &mut formatted)?;
+        }
+        None => write_pattern(parts, "%Y-%m-%d", &mut formatted)?,
+    }
+    context.pad_to(&formatted, Alignment::Left, out)?;
+    Ok(())
+}
+
+/// Write `parts` formatted with the strftime `pattern`.
+fn write_pattern(parts: &Parts, pattern: &str, out: &mut dyn std::fmt::Write) -> CurlyResult<()> {
+    let mut chars = pattern.char_indices();
+    while let Some((start, ch)) = chars.next() {
+        if ch != '%' {
+            out.write_char(ch)?;
+            continue;
+        }
+
+        let mut end = start + 1;
+        for (idx, ch) in chars.by_ref() {
+            end = idx + ch.len_utf8();
+            if ch != ':' && ch != '.' {
+                break;
+            }
+        }
+        let spec = &pattern[start + 1..end];
+
+        match spec {
+            "Y" => write!(out, "{:04}", parts.year)?,
+            "C" => write!(out, "{:02}", parts.year.div_euclid(100))?,
+            "y" => write!(out, "{:02}", parts.year.rem_euclid(100))?,
+            "m" => write!(out, "{:02}", parts.month)?,
+            "b" => out.write_str(&MONTHS[parts.month as usize - 1][..3])?,
+            "B" => out.write_str(MONTHS[parts.month as usize - 1])?,
+            "d" => write!(out, "{:02}", parts.day)?,
+            "e" => write!(out, "{:>2}", parts.day)?,
+            "j" => write!(out, "{:03}", parts.ordinal)?,
+            "a" => out.write_str(&WEEKDAYS[parts.weekday as usize][..3])?,
+            "A" => out.write_str(WEEKDAYS[parts.weekday as usize])?,
+            "u" => write!(out, "{}", parts.weekday + 1)?,
+            "w" => write!(out, "{}", (parts.weekday + 1) % 7)?,
+            "H" => write!(out, "{:02}", parts.time(pattern, spec)?.hour)?,
+            "I" => write!(
+                out,
+                "{:02}",
+                (parts.time(pattern, spec)?.hour + 11) % 12 + 1
+            )?,
+            "M" => write!(out, "{:02}", parts.time(pattern, spec)?.minute)?,
+            "S" => write!(out, "{:02}", parts.time(pattern, spec)?.second)?,
+            "p" | "P" => {
+                let meridiem = match (parts.time(pattern, spec)?.hour < 12, spec) {
+                    (true, "p") => "AM",
+                    (false, "p") => "PM",
+                    (true, _) => "am",
+                    (false, _) => "pm",
+                };
+                out.write_str(meridiem)?;
+            }
+            "f" => write!(out, "{:09}", parts.time(pattern, spec)?.nanosecond)?,
+            ".f" => match parts.time(pattern, spec)?.nanosecond {
+                0 => {}
+                nanos if nanos % 1_000_000 == 0 => write!(out, ".{:03}", nanos / 1_000_000)?,
+                nanos if nanos % 1_000 == 0 => write!(out, ".{:06}", nanos / 1_000)?,
+                nanos => write!(out, ".{nanos:09}")?,
+            },
+            "z" | ":z" => {
+                let offset = parts.offset(pattern, spec)?;
+                let sign = if offset < 0 { '-' } else { '+' };
+                let offset = offset.unsigned_abs();
+                let separator = if spec == ":z" { ":" } else { "" };
+                write!(
+                    out,
+                    "{sign}{:02}{separator}{:02}",
+                    offset / 3600,
+                    offset / 60 % 60
+                )?;
+            }
+            "s" => write!(out, "{}", parts.timestamp)?,
+            "F" => write_pattern(parts, "%Y-%m-%d", out)?,
+            "T" => {
+                parts.time(pattern, spec)?;
+                write_pattern(parts, "%H:%M:%S", out)?;
+            }
+            "D" => write_pattern(parts, "%m/%d/%y", out)?,
+            "R" => {
+                parts.time(pattern, spec)?;
+                write_pattern(parts, "%H:%M", out)?;
+            }
+            "%" => out.write_char('%')?,
+            "n" => out.write_char('\n')?,
+            "t" => out.write_char('\t')?,
+            _ => {
+                return Err(CurlyErrorKind::Generic(CurlyError::from_boxed(format!(
+                    "Invalid specifier `%{spec}` in the pattern `{pattern}`"
+                ))))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Write a duration of `delta` seconds from now in words, such as `3 hours ago` or `in 2 days`.
+fn write_relative(delta: i64, out: &mut dyn std::fmt::Write) -> CurlyResult<()> {
+    const UNITS: [(u64, &str); 6] = [
+        (365 * 24 * 60 * 60, "year"),
+        (30 * 24 * 60 * 60, "month"),
+        (24 * 60 * 60, "day"),
+        (60 * 60, "hour"),
+        (60, "minute"),
+        (1, "second"),
+    ];
+
+    let seconds = delta.unsigned_abs();
+    let Some(&(size, unit)) = UNITS.iter().find(|&&(size, _)| seconds >= size) else {
+        out.write_str("just now")?;
+        return Ok(());
+    };
+    let count = seconds / size;
+
+    let plural = if count == 1 { "" } else { "s" };
+    if delta < 0 {
+        write!(out, "{count} {unit}{plural} ago")?;
+    } else {
+        write!(out, "in {count} {unit}{plural}")?;
+    }
+    Ok(())
+}
+
+/// The current Unix timestamp in seconds.
+fn now() -> i64 {
+    let seconds = match std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
+        Ok(elapsed) => i64::try_from(elapsed.as_secs()),
+        Err(error) => i64::try_from(error.duration().as_secs()).map(|seconds| -seconds),
+    };
+    seconds.unwrap_or(i64::MAX)
+}
+
+/// Implements `CurlyFmt` for a date or time type, given a function from the value to its [`Parts`](Parts).
+macro_rules! datetime_impls {
+    ($(#[cfg(feature = $feature:literal)] <$($generic:ident: $bound:path),*> $ty:ty => $parts:expr),*) => {
+        $(
+            #[cfg(feature = $feature)]
+            impl<$($generic: $bound),*> CurlyFmt for $ty {
+                fn curly_fmt(&self, context: &CurlyContext) -> CurlyFmtResult {
+                    let mut formatted = String::new();
+                    self.curly_write(context, &mut formatted)?;
+                    Ok(formatted)
+                }
+
+                fn curly_write(&self, context: &CurlyContext, out: &mut dyn std::fmt::Write) -> CurlyResult<()> {
+                    let parts: fn(&Self) -> Parts = $parts;
+                    write_parts(&parts(self), context, out)
+                }
+            }
+        )*
+    };
+}
+
+datetime_impls!(
+    #[cfg(feature = "chrono")]
+    <Tz: chrono::TimeZone> chrono::DateTime<Tz> => |value| {
+        use chrono::{Datelike, Offset, Timelike};
+
+        let local = value.naive_local();
+        Parts {
+            year: local.year(),
+            month: local.month(),
+            day: local.day(),
+            ordinal: local.ordinal(),
+            weekday: local.weekday().num_days_from_monday(),
+            time: Some(Time {
+                hour: local.hour(),
+                minute: local.minute(),
+                second: local.second(),
+                nanosecond: local.nanosecond(),
+            }),
+            offset: Some(value.offset().fix().local_minus_utc()),
+            timestamp: value.timestamp(),
+        }
+    },
+    #[cfg(feature = "chrono")]
+    <> chrono::NaiveDate => |value| {
+        use chrono::Datelike;
+
+        // 1970-01-01 is day 719 163 of the common era.
+        let days = i64::from(value.num_days_from_ce()) - 719_163;
+        Parts {
+            year: value.year(),
+            month: value.month(),
+            day: value.day(),
+            ordinal: value.ordinal(),
+            weekday: value.weekday().num_days_from_monday(),
+            time: None,
+            offset: None,
+            timestamp: days * 24 * 60 * 60,
+        }
+    },
+    #[cfg(feature = "time")]
+    <> time::OffsetDateTime => |value| Parts {
+        year: value.year(),
+        month: u32::from(u8::from(value.month())),
+        day: u32::from(value.day()),
+        ordinal: u32::from(value.ordinal()),
+        weekday: u32::from(value.weekday().number_days_from_monday()),
+        time: Some(Time {
+            hour: u32::from(value.hour()),
+            minute: u32::from(value.minute()),
+            second: u32::from(value.second()),
+            nanosecond: value.nanosecond(),
+        }),
+        offset: Some(value.offset().whole_seconds()),
+        timestamp: value.unix_timestamp(),
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    fn format(value: &dyn CurlyFmt, segment: &str) -> CurlyFmtResult {
+        value.curly_fmt(&CurlyContext::from_segment(segment, 1, 1).unwrap())
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn chrono_dates() {
+        use chrono::{FixedOffset, NaiveDate, TimeZone};
+
+        let created = FixedOffset::east_opt(3600)
+            .unwrap()
+            .with_ymd_and_hms(2024, 3, 5, 14, 7, 9)
+            .unwrap();
+        assert_eq!(format(&created, "x").unwrap(), "2024-03-05T14:07:09+01:00");
+        assert_eq!(
+            format(&created, "x:%Y-%m-%d %H:%M").unwrap(),
+            "2024-03-05 14:07"
+        );
+        assert_eq!(
+            format(&created, "x:%a %e %b, %I:%M %p (%j) %%").unwrap(),
+            "Tue  5 Mar, 02:07 PM (065) %"
+        );
+        assert_eq!(
+            format(&created, "x:rfc2822").unwrap(),
+            "Tue, 05 Mar 2024 14:07:09 +0100"
+        );
+        assert_eq!(format(&created, "x:timestamp").unwrap(), "1709644029");
+        assert_eq!(
+            format(
+                &(created + chrono::Duration::milliseconds(250)),
+                "x:rfc3339"
+            )
+            .unwrap(),
+            "2024-03-05T14:07:09.250+01:00"
+        );
+
+        let date = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+        assert_eq!(format(&date, "x:>12").unwrap(), "  2024-03-05");
+        assert_eq!(format(&date, "x:%A %d %B").unwrap(), "Tuesday 05 March");
+        assert_eq!(format(&date, "x:%s").unwrap(), "1709596800");
+        assert_eq!(
+            format(&date, "x:%F %T").unwrap_err().to_string(),
+            "Error: `%T` in the pattern `%F %T` requires a time"
+        );
+        assert_eq!(
+            format(&date, "x:%Q").unwrap_err().to_string(),
+            "Error: Invalid specifier `%Q` in the pattern `%Q`"
+        );
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn time_dates() {
+        use time::{Date, Month, UtcOffset};
+
+        let created = Date::from_calendar_date(2024, Month::March, 5)
+            .unwrap()
+            .with_hms_nano(14, 7, 9, 26_490_000)
+            .unwrap()
+            .assume_offset(UtcOffset::from_hms(-5, -30, 0).unwrap());
+        assert_eq!(
+            format(&created, "x").unwrap(),
+            "2024-03-05T14:07:09.026490-05:30"
+        );
+        assert_eq!(
+            format(&created, "x:%D %R %z %f").unwrap(),
+            "03/05/24 14:07 -0530 026490000"
+        );
+    }
+
+    #[cfg(any(feature = "chrono", feature = "time"))]
+    #[test]
+    fn relative() {
+        let mut relative = String::new();
+        for delta in &[-10_800, -59, 0, 1, 86_400 * 2 + 5, -86_400 * 400] {
+            super::write_relative(*delta, &mut relative).unwrap();
+            relative.push_str(", ");
+        }
+        assert_eq!(
+            relative,
+            "3 hours ago, 59 seconds ago, just now, in 1 second, in 2 days, 1 year ago, "
+        );
+    }
+}
@@ -0,0 +1,323 @@
+//! [`Provider`](Provider) implementations for documents loaded at runtime, such as config files.
+//!
+//! | Feature | Type |
+//! |---------|------|
+//! | `json` | [`serde_json::Value`](https://docs.rs/serde_json/1/serde_json/enum.Value.html) |
+//! | `toml` | [`toml::Value`](https://docs.rs/toml/0.8/toml/enum.Value.html) |
+//! | `yaml` | [`serde_yaml::Value`](https://docs.rs/serde_yaml/0.9/serde_yaml/enum.Value.html) |
+//!
+//! Like [`SerdeProvider`](crate::serialize::SerdeProvider), table entries are provided by name and
+//! array items by index, with dotted paths for nested values (`{server.ports.0}`), and arrays can be
+//! iterated over by loop sections. Integers and floats are formatted like Rust integers and floats,
+//! so `{port:x}` and `{ratio:.2}` work as expected. Null values are formatted as empty strings.
+//!
+//! # Example
+//!
+//! ```rust
+//! # #[cfg(feature = "json")] {
+//! use curly::Template;
+//!
+//! let config: serde_json::Value =
+//!     serde_json::from_str(r#"{"server": {"port": 8080, "ratio": 0.5}}"#).unwrap();
+//! let template = Template::parse("{server.port:#x} {server.ratio:.2}").unwrap();
+//! assert_eq!(template.render(&config).unwrap(), "0x1f90 0.50");
+//! # }
+//! ```
+
+use crate::prelude::*;
+use crate::{Provider, ProviderIter};
+
+/// A scalar value within a document.
+///
+/// Not every document format has every kind of scalar, e.g. TOML has no nulls.
+#[allow(dead_code)]
+enum Scalar<'a> {
+    Null,
+    Bool(bool),
+    Signed(i64),
+    Unsigned(u64),
+    Float(f64),
+    String(&'a str),
+    /// Any other scalar, such as a TOML date, which is formatted like a string.
+    Other(String),
+}
+
+/// The shape of a value within a document.
+enum Shape<'a, V> {
+    Scalar(Scalar<'a>),
+    Array(&'a [V]),
+    Table { is_empty: bool },
+}
+
+/// A value within a document.
+trait Document: Provider + Sized {
+    /// Get the entry `part` of a table, or the item at index `part` of an array.
+    fn child(&self, part: &str) -> Option<&Self>;
+
+    fn shape(&self) -> Shape<'_, Self>;
+}
+
+/// Find the value at the dotted path `key`.
+fn lookup<'a, V: Document>(root: &'a V, key: &str) -> CurlyResult<&'a V> {
+    key.split('.')
+        .try_fold(root, |value, part| value.child(part))
+        .ok_or_else(|| {
+            CurlyErrorKind::Generic(CurlyError::from_boxed(format!(
+                "Invalid format specifier `{key}`"
+            )))
+        })
+}
+
+fn provide_to<V: Document>(
+    root: &V,
+    context: &CurlyContext,
+    key: &str,
+    out: &mut dyn std::fmt::Write,
+) -> CurlyResult<()> {
+    match lookup(root, key)?.shape() {
+        Shape::Scalar(Scalar::Null) => "".curly_write(context, out),
+        Shape::Scalar(Scalar::Bool(value)) => value.curly_write(context, out),
+        Shape::Scalar(Scalar::Signed(value)) => value.curly_write(context, out),
+        Shape::Scalar(Scalar::Unsigned(value)) => value.curly_write(context, out),
+        Shape::Scalar(Scalar::Float(value)) => value.curly_write(context, out),
+        Shape::Scalar(Scalar::String(value)) => value.curly_write(context, out),
+        Shape::Scalar(Scalar::Other(value)) => value.curly_write(context, out),
+        Shape::Array(_) | Shape::Table { .. } => {
+            Err(CurlyErrorKind::Generic(CurlyError::from_boxed(format!(
+                "Cannot format `{key}`, which is not a scalar value"
+            ))))
+        }
+    }
+}
+
+fn is_truthy<V: Document>(root: &V, key: &str) -> CurlyResult<bool> {
+    Ok(match lookup(root, key)?.shape() {
+        Shape::Scalar(Scalar::Null) => false,
+        Shape::Scalar(Scalar::Bool(value)) => value,
+        Shape::Scalar(Scalar::Signed(value)) => value.is_truthy(),
+        Shape::Scalar(Scalar::Unsigned(value)) => value.is_truthy(),
+        Shape::Scalar(Scalar::Float(value)) => value.is_truthy(),
+        Shape::Scalar(Scalar::String(value)) => value.is_truthy(),
+        Shape::Scalar(Scalar::Other(value)) => value.is_truthy(),
+        Shape::Array(items) => !items.is_empty(),
+        Shape::Table { is_empty } => !is_empty,
+    })
+}
+
+fn provide_each<'a, V: Document>(root: &'a V, key: &str) -> CurlyResult<ProviderIter<'a>> {
+    match lookup(root, key).map(Document::shape) {
+        Ok(Shape::Array(items)) => Ok(Box::new(items.iter().map(|item| item as &dyn Provider))),
+        _ => Err(CurlyErrorKind::Generic(CurlyError::from_boxed(format!(
+            "Cannot iterate over `{key}`"
+        )))),
+    }
+}
+
+macro_rules! document_provider {
+    ($($feature:literal => $ty:ty),*) => {
+        $(
+            #[cfg(feature = $feature)]
+            impl Provider for $ty {
+                fn provide(&self, context: &CurlyContext, key: &str) -> CurlyFmtResult {
+                    let mut provided = String::new();
+                    provide_to(self, context, key, &mut provided)?;
+                    Ok(provided)
+                }
+
+                fn provide_to(
+                    &self,
+                    context: &CurlyContext,
+                    key: &str,
+                    out: &mut dyn std::fmt::Write,
+                ) -> CurlyResult<()> {
+                    provide_to(self, context, key, out)
+                }
+
+                fn is_truthy(&self, key: &str) -> CurlyResult<bool> {
+                    is_truthy(self, key)
+                }
+
+                fn provide_each(&self, key: &str) -> CurlyResult<ProviderIter<'_>> {
+                    provide_each(self, key)
+                }
+
+                fn contains_key(&self, key: &str) -> bool {
+                    lookup(self, key).is_ok()
+                }
+            }
+        )*
+    };
+}
+
+document_provider!(
+    "json" => serde_json::Value,
+    "toml" => toml::Value,
+    "yaml" => serde_yaml::Value
+);
+
+#[cfg(feature = "json")]
+impl Document for serde_json::Value {
+    fn child(&self, part: &str) -> Option<&Self> {
+        match self {
+            serde_json::Value::Object(entries) => entries.get(part),
+            serde_json::Value::Array(items) => items.get(part.parse::<usize>().ok()?),
+            _ => None,
+        }
+    }
+
+    fn shape(&self) -> Shape<'_, Self> {
+        Shape::Scalar(match self {
+            serde_json::Value::Null => Scalar::Null,
+            serde_json::Value::Bool(value) => Scalar::Bool(*value),
+            serde_json::Value::Number(number) => match (number.as_i64(), number.as_u64()) {
+                (Some(value), _) => Scalar::Signed(value),
+                (None, Some(value)) => Scalar::Unsigned(value),
+                (None, None) => Scalar::Float(number.as_f64().unwrap_or(f64::NAN)),
+            },
+            serde_json::Value::String(value) => Scalar::String(value),
+            serde_json::Value::Array(items) => return Shape::Array(items),
+            serde_json::Value::Object(entries) => {
+                return Shape::Table {
+                    is_empty: entries.is_empty(),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(feature = "toml")]
+impl Document for toml::Value {
+    fn child(&self, part: &str) -> Option<&Self> {
+        match self {
+            toml::Value::Table(entries) => entries.get(part),
+            toml::Value::Array(items) => items.get(part.parse::<usize>().ok()?),
+            _ => None,
+        }
+    }
+
+    fn shape(&self) -> Shape<'_, Self> {
+        Shape::Scalar(match self {
+            toml::Value::Boolean(value) => Scalar::Bool(*value),
+            toml::Value::Integer(value) => Scalar::Signed(*value),
+            toml::Value::Float(value) => Scalar::Float(*value),
+            toml::Value::String(value) => Scalar::String(value),
+            toml::Value::Datetime(value) => Scalar::Other(value.to_string()),
+            toml::Value::Array(items) => return Shape::Array(items),
+            toml::Value::Table(entries) => {
+                return Shape::Table {
+                    is_empty: entries.is_empty(),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(feature = "yaml")]
+impl Document for serde_yaml::Value {
+    fn child(&self, part: &str) -> Option<&Self> {
+        match self {
+            serde_yaml::Value::Mapping(entries) => entries.get(part),
+            serde_yaml::Value::Sequence(items) => items.get(part.parse::<usize>().ok()?),
+            serde_yaml::Value::Tagged(tagged) => tagged.value.child(part),
+            _ => None,
+        }
+    }
+
+    fn shape(&self) -> Shape<'_, Self> {
+        Shape::Scalar(match self {
+            serde_yaml::Value::Null => Scalar::Null,
+            serde_yaml::Value::Bool(value) => Scalar::Bool(*value),
+            serde_yaml::Value::Number(number) => match (number.as_i64(), number.as_u64()) {
+                (Some(value), _) => Scalar::Signed(value),
+                (None, Some(value)) => Scalar::Unsigned(value),
+                (None, None) => Scalar::Float(number.as_f64().unwrap_or(f64::NAN)),
+            },
+            serde_yaml::Value::String(value) => Scalar::String(value),
+            serde_yaml::Value::Sequence(items) => return Shape::Array(items),
+            serde_yaml::Value::Mapping(entries) => {
+                return Shape::Table {
+                    is_empty: entries.is_empty(),
+                }
+            }
+            serde_yaml::Value::Tagged(tagged) => return tagged.value.shape(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use crate::{Provider, Template};
+
+    fn render(format_string: &str, document: &dyn Provider) -> CurlyFmtResult {
+        Template::parse(format_string)?.render(document)
+    }
+
+    const FORMAT: &str = "{name:>5}:{port:x} {ratio:.2} {offset:+} {big} [{missing}] {hosts.1.0} \
+                          {#each hosts as host}{host.0}{#sep},{/each}{#if debug}!{/if}";
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_documents() {
+        let document: serde_json::Value = serde_json::from_str(
+            r#"{"name": "web", "port": 8080, "ratio": 0.25, "offset": -1, "big": 18446744073709551615,
+                "missing": null, "hosts": [["a"], ["b"]], "debug": false}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            render(FORMAT, &document).unwrap(),
+            "  web:1f90 0.25 -1 18446744073709551615 [] b a,b"
+        );
+        assert_eq!(
+            render("{hosts}", &document).unwrap_err().to_string(),
+            "Error: Cannot format `hosts`, which is not a scalar value"
+        );
+        assert!(render("{ratio:x}", &document).is_err());
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml_documents() {
+        let document: toml::Value = toml::from_str(
+            r#"
+                name = "web"
+                port = 8080
+                ratio = 0.25
+                offset = -1
+                big = 1979-05-27
+                missing = ""
+                hosts = [["a"], ["b"]]
+                debug = true
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            render(FORMAT, &document).unwrap(),
+            "  web:1f90 0.25 -1 1979-05-27 [] b a,b!"
+        );
+        assert!(render("{nope}", &document).is_err());
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn yaml_documents() {
+        let document: serde_yaml::Value = serde_yaml::from_str(
+            "
+            name: web
+            port: 8080
+            ratio: 0.25
+            offset: -1
+            big: !Big 7
+            missing: ~
+            hosts: [[a], [b]]
+            debug: true
+            ",
+        )
+        .unwrap();
+        assert_eq!(
+            render(FORMAT, &document).unwrap(),
+            "  web:1f90 0.25 -1 7 [] b a,b!"
+        );
+        assert!(render("{#each name}{/each}", &document).is_err());
+    }
+}
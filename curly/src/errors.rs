@@ -4,6 +4,8 @@ pub enum CurlyErrorKind {
     Generic(Box<dyn Error>),
     Syntax(Box<dyn Error>),
     Internal(Box<dyn Error>),
+    Write(Box<dyn Error>),
+    LimitExceeded(Box<dyn Error>),
 }
 
 impl std::fmt::Display for CurlyErrorKind {
@@ -21,10 +23,30 @@ impl std::fmt::Display for CurlyErrorKind {
                 fmt.write_str("Internal Error: ")?;
                 e.fmt(fmt)
             }
+            CurlyErrorKind::Write(e) => {
+                fmt.write_str("Write Error: ")?;
+                e.fmt(fmt)
+            }
+            CurlyErrorKind::LimitExceeded(e) => {
+                fmt.write_str("Limit Exceeded: ")?;
+                e.fmt(fmt)
+            }
         }
     }
 }
 
+impl From<std::fmt::Error> for CurlyErrorKind {
+    fn from(error: std::fmt::Error) -> Self {
+        CurlyErrorKind::Write(Box::new(error))
+    }
+}
+
+impl From<std::io::Error> for CurlyErrorKind {
+    fn from(error: std::io::Error) -> Self {
+        CurlyErrorKind::Write(Box::new(error))
+    }
+}
+
 #[derive(Debug, Eq, PartialEq, Clone, Hash, Default)]
 pub struct CurlyError {
     msg: String,
@@ -0,0 +1,241 @@
+//! Escaping of substituted values, for templates that produce HTML, shell commands, etc...
+//!
+//! A [`Template`](crate::Template) with an [`Escaping`](Escaping) escapes the value of every
+//! placeholder, but not its literal text. Values can opt out of escaping with the `safe` filter
+//! (`{raw_html | safe}`), or by being a [`SafeString`](SafeString).
+
+use crate::prelude::*;
+use std::fmt::Write;
+use std::sync::Arc;
+
+/// Escapes substituted values for an output format.
+pub trait Escaper: std::fmt::Debug + Send + Sync {
+    /// Write `value`, escaped, to `out`.
+    ///
+    /// # Errors
+    ///
+    /// If writing to `out` failed.
+    fn escape(&self, value: &str, out: &mut dyn Write) -> std::fmt::Result;
+}
+
+/// The escaping applied to the values substituted into a template.
+#[derive(Debug, Clone, Default)]
+pub enum Escaping {
+    /// Values are substituted as is.
+    #[default]
+    None,
+    /// Escapes `&`, `<`, `>`, `"` and `'` as HTML character references.
+    Html,
+    /// Escapes `&`, `<`, `>`, `"` and `'` as XML entities.
+    Xml,
+    /// Escapes values for use within a JSON string (without the surrounding quotes).
+    Json,
+    /// Quotes values as a single POSIX shell word.
+    Shell,
+    /// Quotes values as a CSV field if they contain a `,`, `"` or line break.
+    Csv,
+    /// Escapes values with a custom [`Escaper`](Escaper).
+    Custom(Arc<dyn Escaper>),
+}
+
+impl Escaping {
+    /// Escape values with a custom [`Escaper`](Escaper).
+    pub fn custom(escaper: impl Escaper + 'static) -> Self {
+        Escaping::Custom(Arc::new(escaper))
+    }
+
+    /// Whether values are substituted as is.
+    pub fn is_none(&self) -> bool {
+        matches!(self, Escaping::None)
+    }
+}
+
+impl Escaper for Escaping {
+    fn escape(&self, value: &str, out: &mut dyn Write) -> std::fmt::Result {
+        match self {
+            Escaping::Html => escape_markup(value, "&#x27;", out),
+            Escaping::Xml => escape_markup(value, "&apos;", out),
+            Escaping::Json => escape_json(value, out),
+            Escaping::Shell => {
+                out.write_char('\'')?;
+                out.write_str(&value.replace('\'', r"'\''"))?;
+                out.write_char('\'')
+            }
+            Escaping::Csv if value.contains([',', '"', '\n', '\r']) => {
+                out.write_char('"')?;
+                out.write_str(&value.replace('"', "\"\""))?;
+                out.write_char('"')
+            }
+            Escaping::None | Escaping::Csv => out.write_str(value),
+            Escaping::Custom(escaper) => escaper.escape(value, out),
+        }
+    }
+}
+
+/// Custom escapings are equal if they share the same escaper.
+impl PartialEq for Escaping {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Escaping::Custom(escaper), Escaping::Custom(other)) => {
+                std::ptr::addr_eq(Arc::as_ptr(escaper), Arc::as_ptr(other))
+            }
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
+impl Eq for Escaping {}
+
+impl std::hash::Hash for Escaping {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        if let Escaping::Custom(escaper) = self {
+            Arc::as_ptr(escaper).cast::<()>().hash(state);
+        }
+    }
+}
+
+fn escape_markup(value: &str, apostrophe: &str, out: &mut dyn Write) -> std::fmt::Result {
+    let mut rest = value;
+    while let Some(idx) = rest.find(['&', '<', '>', '"', '\'']) {
+        out.write_str(&rest[..idx])?;
+        out.write_str(match rest.as_bytes()[idx] {
+            b'&' => "&amp;",
+            b'<' => "&lt;",
+            b'>' => "&gt;",
+            b'"' => "&quot;",
+            _ => apostrophe,
+        })?;
+        rest = &rest[idx + 1..];
+    }
+    out.write_str(rest)
+}
+
+/// Escape `value` for use within a JSON string.
+pub(crate) fn escape_json(value: &str, out: &mut dyn Write) -> std::fmt::Result {
+    for ch in value.chars() {
+        match ch {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            '\n' => out.write_str("\\n")?,
+            '\r' => out.write_str("\\r")?,
+            '\t' => out.write_str("\\t")?,
+            ch if ch.is_control() => write!(out, "\\u{:04x}", ch as u32)?,
+            ch => out.write_char(ch)?,
+        }
+    }
+    Ok(())
+}
+
+/// A string that is already safe for the output format, so it's never escaped.
+///
+/// # Example
+///
+/// ```rust
+/// # #[macro_use] extern crate curly;
+/// use curly::escaping::{Escaping, SafeString};
+/// use curly::Template;
+///
+/// #[derive(Provider)]
+/// struct Post {
+///     title: String,
+///     body: SafeString,
+/// }
+///
+/// # fn main() {
+/// let template = Template::parse("<h1>{title}</h1>{body}")
+///     .unwrap()
+///     .with_escaping(Escaping::Html);
+/// let post = Post {
+///     title: String::from("Fish & Chips"),
+///     body: SafeString::from("<p>Yum!</p>"),
+/// };
+/// assert_eq!(template.render(&post).unwrap(), "<h1>Fish &amp; Chips</h1><p>Yum!</p>");
+/// # }
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Default)]
+pub struct SafeString(pub String);
+
+impl SafeString {
+    /// The string, which is safe for the output format.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for SafeString {
+    fn from(value: String) -> Self {
+        SafeString(value)
+    }
+}
+
+impl From<&str> for SafeString {
+    fn from(value: &str) -> Self {
+        SafeString(value.to_string())
+    }
+}
+
+impl CurlyFmt for SafeString {
+    fn curly_fmt(&self, context: &CurlyContext) -> CurlyFmtResult {
+        self.0.curly_fmt(context)
+    }
+
+    fn curly_write(&self, context: &CurlyContext, out: &mut dyn Write) -> CurlyResult<()> {
+        self.0.curly_write(context, out)
+    }
+
+    fn is_safe(&self) -> bool {
+        true
+    }
+
+    fn curly_is_truthy(&self) -> CurlyResult<bool> {
+        Ok(!self.0.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn escape(escaping: &Escaping, value: &str) -> String {
+        let mut escaped = String::new();
+        escaping.escape(value, &mut escaped).unwrap();
+        escaped
+    }
+
+    #[test]
+    fn builtin_escapers() {
+        let value = r#"<a href="x">Tom & Jerry's</a>"#;
+        assert_eq!(
+            escape(&Escaping::Html, value),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#x27;s&lt;/a&gt;"
+        );
+        assert_eq!(escape(&Escaping::Xml, "'a' < b"), "&apos;a&apos; &lt; b");
+        assert_eq!(escape(&Escaping::Json, "a \"b\"\n"), r#"a \"b\"\n"#);
+        assert_eq!(
+            escape(&Escaping::Shell, "it's; rm -rf"),
+            r"'it'\''s; rm -rf'"
+        );
+        assert_eq!(escape(&Escaping::Csv, "plain"), "plain");
+        assert_eq!(escape(&Escaping::Csv, "a, \"b\""), r#""a, ""b""""#);
+        assert_eq!(escape(&Escaping::None, "<&>"), "<&>");
+    }
+
+    #[test]
+    fn custom_escaper() {
+        #[derive(Debug)]
+        struct Upper;
+
+        impl Escaper for Upper {
+            fn escape(&self, value: &str, out: &mut dyn Write) -> std::fmt::Result {
+                out.write_str(&value.to_uppercase())
+            }
+        }
+
+        let escaping = Escaping::custom(Upper);
+        assert_eq!(escape(&escaping, "abc"), "ABC");
+        assert_eq!(escaping, escaping.clone());
+        assert_ne!(escaping, Escaping::custom(Upper));
+        assert_eq!(Escaping::Html, Escaping::Html);
+    }
+}
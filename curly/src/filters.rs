@@ -0,0 +1,287 @@
+//! Filters, which transform the formatted value of a placeholder (`{name | upper | truncate:20}`).
+//!
+//! Filters are applied in order, after the value has been formatted with its flags.
+//! They're looked up by name in a [`FilterRegistry`](FilterRegistry), which contains
+//! the built-in filters by default:
+//!
+//! | Filter | Description |
+//! |--------|-------------|
+//! | `upper` | Converts to uppercase |
+//! | `lower` | Converts to lowercase |
+//! | `title` | Capitalizes the first letter of every word, and lowercases the rest |
+//! | `trim` | Removes leading and trailing whitespace |
+//! | `truncate:N[,suffix]` | Keeps the first `N` characters, followed by `suffix` if anything was removed |
+//! | `replace:from,to` | Replaces every occurrence of `from` with `to` |
+//! | `default:value` | Replaces an empty value with `value` |
+//! | `json` | Quotes and escapes the value as a JSON string |
+//! | `safe` | Marks the value as safe, so it isn't escaped (see [`escaping`](crate::escaping)) |
+
+use crate::limits::RenderLimits;
+use crate::parsing::FilterCall;
+use crate::prelude::*;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// A named transform over the formatted value of a placeholder.
+///
+/// This is implemented for closures, so most filters can be registered without a new type:
+///
+/// ```rust
+/// use curly::filters::FilterRegistry;
+///
+/// let mut filters = FilterRegistry::new();
+/// filters.register("reverse", |value: String, _arguments: &[String]| {
+///     Ok(value.chars().rev().collect())
+/// });
+/// ```
+pub trait Filter: Send + Sync {
+    /// Apply this filter to `value`, the output of the placeholder (or of the previous filter).
+    ///
+    /// # Errors
+    ///
+    /// Any error while filtering, such as invalid `arguments`.
+    fn apply(&self, value: String, arguments: &[String]) -> CurlyFmtResult;
+}
+
+impl<F> Filter for F
+where
+    F: Fn(String, &[String]) -> CurlyFmtResult + Send + Sync,
+{
+    fn apply(&self, value: String, arguments: &[String]) -> CurlyFmtResult {
+        self(value, arguments)
+    }
+}
+
+/// The filters available to a template when rendering, by name.
+pub struct FilterRegistry {
+    filters: HashMap<String, Box<dyn Filter>>,
+}
+
+impl FilterRegistry {
+    /// Create a registry with the built-in filters.
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+        registry
+            .register("upper", |value: String, arguments: &[String]| {
+                expect_arguments("upper", arguments, 0, 0)?;
+                Ok(value.to_uppercase())
+            })
+            .register("lower", |value: String, arguments: &[String]| {
+                expect_arguments("lower", arguments, 0, 0)?;
+                Ok(value.to_lowercase())
+            })
+            .register("title", |value: String, arguments: &[String]| {
+                expect_arguments("title", arguments, 0, 0)?;
+                Ok(title_case(&value))
+            })
+            .register("trim", |value: String, arguments: &[String]| {
+                expect_arguments("trim", arguments, 0, 0)?;
+                Ok(value.trim().to_string())
+            })
+            .register("truncate", |value: String, arguments: &[String]| {
+                expect_arguments("truncate", arguments, 1, 2)?;
+                let len = arguments[0].parse::<usize>().map_err(|_| {
+                    filter_error(format!(
+                        "Invalid argument for filter `truncate`: expected an unsigned integer, found `{}`",
+                        arguments[0]
+                    ))
+                })?;
+                Ok(match value.char_indices().nth(len) {
+                    Some((idx, _)) => {
+                        let suffix = arguments.get(1).map_or("", String::as_str);
+                        format!("{}{suffix}", &value[..idx])
+                    }
+                    None => value,
+                })
+            })
+            .register("replace", |value: String, arguments: &[String]| {
+                expect_arguments("replace", arguments, 2, 2)?;
+                Ok(value.replace(&arguments[0], &arguments[1]))
+            })
+            .register("default", |value: String, arguments: &[String]| {
+                expect_arguments("default", arguments, 1, 1)?;
+                Ok(if value.is_empty() {
+                    arguments[0].clone()
+                } else {
+                    value
+                })
+            })
+            .register("json", |value: String, arguments: &[String]| {
+                expect_arguments("json", arguments, 0, 0)?;
+                Ok(json_string(&value))
+            })
+            .register("safe", |value: String, arguments: &[String]| {
+                expect_arguments("safe", arguments, 0, 0)?;
+                Ok(value)
+            });
+        registry
+    }
+
+    /// Create a registry without any filters.
+    pub fn empty() -> Self {
+        Self {
+            filters: HashMap::new(),
+        }
+    }
+
+    /// The shared registry of built-in filters, used by [`Template::render`](crate::Template::render).
+    pub fn builtin() -> &'static Self {
+        static BUILTIN: OnceLock<FilterRegistry> = OnceLock::new();
+        BUILTIN.get_or_init(Self::new)
+    }
+
+    /// Register `filter` as `name`, replacing any filter with the same name.
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        filter: impl Filter + 'static,
+    ) -> &mut Self {
+        self.filters.insert(name.into(), Box::new(filter));
+        self
+    }
+
+    /// Get the filter registered as `name`.
+    pub fn get(&self, name: &str) -> Option<&dyn Filter> {
+        self.filters.get(name).map(AsRef::as_ref)
+    }
+
+    /// Apply the filters of a placeholder to its formatted `value`, in order.
+    ///
+    /// The result of every filter is checked against `max_bytes`, so a chain of filters
+    /// can't grow a value without bound before it is written.
+    pub(crate) fn apply(
+        &self,
+        filters: &[FilterCall],
+        value: String,
+        max_bytes: Option<usize>,
+    ) -> CurlyFmtResult {
+        filters.iter().try_fold(value, |value, call| {
+            let value = self
+                .get(&call.name)
+                .ok_or_else(|| filter_error(format!("Unknown filter `{}`", call.name)))?
+                .apply(value, &call.arguments)?;
+            let what = format!("Output of filter `{}`", call.name);
+            RenderLimits::check(value.len(), max_bytes, &what)?;
+            Ok(value)
+        })
+    }
+}
+
+impl Default for FilterRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for FilterRegistry {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut names: Vec<_> = self.filters.keys().collect();
+        names.sort();
+        fmt.debug_set().entries(names).finish()
+    }
+}
+
+fn filter_error(message: String) -> CurlyErrorKind {
+    CurlyErrorKind::Generic(CurlyError::from_boxed(message))
+}
+
+/// Check that a filter was given between `min` and `max` arguments.
+fn expect_arguments(name: &str, arguments: &[String], min: usize, max: usize) -> CurlyResult<()> {
+    if (min..=max).contains(&arguments.len()) {
+        return Ok(());
+    }
+
+    let expected = if min == max {
+        min.to_string()
+    } else {
+        format!("{min} to {max}")
+    };
+    Err(filter_error(format!(
+        "Filter `{name}` expects {expected} argument(s), found {}",
+        arguments.len()
+    )))
+}
+
+fn title_case(value: &str) -> String {
+    let mut titled = String::with_capacity(value.len());
+    let mut word_start = true;
+    for ch in value.chars() {
+        if word_start {
+            titled.extend(ch.to_uppercase());
+        } else {
+            titled.extend(ch.to_lowercase());
+        }
+        word_start = ch.is_whitespace();
+    }
+    titled
+}
+
+/// Quote and escape `value` as a JSON string.
+pub(crate) fn json_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    // Writing to a `String` never fails.
+    let _ = crate::escaping::escape_json(value, &mut quoted);
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(segment: &str, value: &str) -> CurlyFmtResult {
+        let placeholder = crate::parsing::parse_segment(segment, 1, 1).unwrap();
+        FilterRegistry::builtin().apply(&placeholder.filters, value.to_string(), None)
+    }
+
+    #[test]
+    fn builtin_filters() {
+        assert_eq!(apply("x | upper", "abc").unwrap(), "ABC");
+        assert_eq!(
+            apply("x | lower | title", "hELLO wORLD").unwrap(),
+            "Hello World"
+        );
+        assert_eq!(
+            apply("x | trim | truncate:5,…", "  a long value ").unwrap(),
+            "a lon…"
+        );
+        assert_eq!(apply("x | truncate:20", "short").unwrap(), "short");
+        assert_eq!(apply(r#"x | replace:", ",/"#, "a, b, c").unwrap(), "a/b/c");
+        assert_eq!(apply("x | default:n/a", "").unwrap(), "n/a");
+        assert_eq!(
+            apply("x | json", "say \"hi\"\n").unwrap(),
+            r#""say \"hi\"\n""#
+        );
+    }
+
+    #[test]
+    fn filter_errors() {
+        assert_eq!(
+            apply("x | truncate", "abc").unwrap_err().to_string(),
+            "Error: Filter `truncate` expects 1 to 2 argument(s), found 0"
+        );
+        assert!(apply("x | truncate:many", "abc").is_err());
+        assert_eq!(
+            apply("x | shout", "abc").unwrap_err().to_string(),
+            "Error: Unknown filter `shout`"
+        );
+    }
+
+    #[test]
+    fn custom_filters() {
+        let mut filters = FilterRegistry::empty();
+        filters.register("reverse", |value: String, _arguments: &[String]| {
+            Ok(value.chars().rev().collect())
+        });
+
+        let placeholder = crate::parsing::parse_segment("x | reverse", 1, 1).unwrap();
+        assert_eq!(
+            filters
+                .apply(&placeholder.filters, String::from("abc"), None)
+                .unwrap(),
+            "cba"
+        );
+        assert!(filters.get("upper").is_none());
+    }
+}
@@ -1,13 +1,578 @@
+use crate::locale::group_digits;
 use crate::prelude::*;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::rc::Rc;
+use std::sync::Arc;
 
 impl CurlyFmt for String {
-    fn curly_fmt(&self, _context: &CurlyContext) -> CurlyFmtResult {
-        Ok(self.clone())
+    fn curly_fmt(&self, context: &CurlyContext) -> CurlyFmtResult {
+        self.as_str().curly_fmt(context)
+    }
+
+    fn curly_write(
+        &self,
+        context: &CurlyContext,
+        out: &mut dyn std::fmt::Write,
+    ) -> CurlyResult<()> {
+        self.as_str().curly_write(context, out)
+    }
+
+    fn curly_is_truthy(&self) -> CurlyResult<bool> {
+        Ok(!self.is_empty())
     }
 }
 
 impl CurlyFmt for str {
-    fn curly_fmt(&self, _context: &CurlyContext) -> CurlyFmtResult {
-        Ok(self.to_string())
+    fn curly_fmt(&self, context: &CurlyContext) -> CurlyFmtResult {
+        Ok(context.pad(self, Alignment::Left))
+    }
+
+    fn curly_write(
+        &self,
+        context: &CurlyContext,
+        out: &mut dyn std::fmt::Write,
+    ) -> CurlyResult<()> {
+        context.pad_to(self, Alignment::Left, out)?;
+        Ok(())
+    }
+
+    fn curly_is_truthy(&self) -> CurlyResult<bool> {
+        Ok(!self.is_empty())
+    }
+}
+
+/// Formats as `true` or `false`, or as one of the renderings in a `true/false` pattern,
+/// such as `{enabled:yes/no}` or `{passed:>2✓/✗}`.
+impl CurlyFmt for bool {
+    fn curly_fmt(&self, context: &CurlyContext) -> CurlyFmtResult {
+        let mut formatted = String::new();
+        self.curly_write(context, &mut formatted)?;
+        Ok(formatted)
+    }
+
+    fn curly_write(
+        &self,
+        context: &CurlyContext,
+        out: &mut dyn std::fmt::Write,
+    ) -> CurlyResult<()> {
+        let (yes, no) = match context.custom_pattern() {
+            Some(pattern) => pattern.split_once('/').ok_or_else(|| {
+                CurlyErrorKind::Generic(CurlyError::from_boxed(format!(
+                    "Invalid pattern `{pattern}` for `bool`, expected `true/false` renderings such as `yes/no`"
+                )))
+            })?,
+            None => ("true", "false"),
+        };
+        context.pad_to(if *self { yes } else { no }, Alignment::Left, out)?;
+        Ok(())
+    }
+
+    fn curly_is_truthy(&self) -> CurlyResult<bool> {
+        Ok(*self)
+    }
+}
+
+impl CurlyFmt for char {
+    fn curly_fmt(&self, context: &CurlyContext) -> CurlyFmtResult {
+        self.encode_utf8(&mut [0; 4]).curly_fmt(context)
+    }
+
+    fn curly_write(
+        &self,
+        context: &CurlyContext,
+        out: &mut dyn std::fmt::Write,
+    ) -> CurlyResult<()> {
+        self.encode_utf8(&mut [0; 4]).curly_write(context, out)
+    }
+}
+
+/// Formats as an empty string, padded to the width.
+impl CurlyFmt for () {
+    fn curly_fmt(&self, context: &CurlyContext) -> CurlyFmtResult {
+        "".curly_fmt(context)
+    }
+
+    fn curly_write(
+        &self,
+        context: &CurlyContext,
+        out: &mut dyn std::fmt::Write,
+    ) -> CurlyResult<()> {
+        "".curly_write(context, out)
+    }
+}
+
+/// An error for a number type that is not supported by a type, e.g. `{float:x}`
+fn unsupported_number_type(context: &CurlyContext, type_name: &str) -> CurlyErrorKind {
+    CurlyErrorKind::Generic(CurlyError::from_boxed(format!(
+        "Number type {:?} is not supported for `{}`",
+        context.flags.number_type, type_name
+    )))
+}
+
+/// Apply the locale of `context` to the formatted number `digits`, grouping the digits of its integer
+/// part if the grouping flag is set, and replacing its decimal point with the locale's decimal separator.
+///
+/// Numbers in other bases than 10 are grouped every 4 digits.
+fn localize(context: &CurlyContext, digits: String) -> String {
+    let decimal = matches!(
+        context.flags.number_type,
+        NumberType::Normal | NumberType::LowerExp | NumberType::UpperExp
+    );
+    let locale = &context.locale;
+    let grouping = match (context.flags.grouping, decimal) {
+        (None, _) => None,
+        (Some(Grouping::Locale), true) => Some((locale.group_separator(), locale.group_sizes())),
+        (Some(Grouping::Locale), false) => Some((locale.group_separator(), (4, 4))),
+        (Some(Grouping::Underscore), true) => Some(('_', (3, 3))),
+        (Some(Grouping::Underscore), false) => Some(('_', (4, 4))),
+    };
+    if grouping.is_none() && (!decimal || locale.decimal_separator() == '.') {
+        return digits;
+    }
+
+    let end = digits
+        .find(|ch: char| !ch.is_ascii_hexdigit() || (decimal && !ch.is_ascii_digit()))
+        .unwrap_or(digits.len());
+    let (integer, rest) = digits.split_at(end);
+    let mut localized = match grouping {
+        Some((separator, sizes)) => group_digits(integer, separator, sizes),
+        None => integer.to_string(),
+    };
+    match rest.strip_prefix('.') {
+        Some(fraction) if decimal => {
+            localized.push(locale.decimal_separator());
+            localized.push_str(fraction);
+        }
+        _ => localized.push_str(rest),
+    }
+    localized
+}
+
+macro_rules! integer_impls {
+    (unsigned: $($ty:ty),*) => {
+        $(integer_impls!(@impl $ty, |value: $ty| value);)*
+    };
+    (signed: $($ty:ty),*) => {
+        $(integer_impls!(@impl $ty, |value: $ty| value.unsigned_abs());)*
+    };
+    (@impl $ty:ty, $abs:expr) => {
+        impl CurlyFmt for $ty {
+            fn curly_fmt(&self, context: &CurlyContext) -> CurlyFmtResult {
+                let mut formatted = String::new();
+                self.curly_write(context, &mut formatted)?;
+                Ok(formatted)
+            }
+
+            #[allow(unused_comparisons)]
+            fn curly_write(&self, context: &CurlyContext, out: &mut dyn std::fmt::Write) -> CurlyResult<()> {
+                let is_nonnegative = *self >= 0;
+                let abs = ($abs)(*self);
+                let (prefix, digits) = match context.flags.number_type {
+                    NumberType::Normal => ("", abs.to_string()),
+                    NumberType::Octal => ("0o", format!("{:o}", abs)),
+                    NumberType::LowerHex => ("0x", format!("{:x}", abs)),
+                    NumberType::UpperHex => ("0x", format!("{:X}", abs)),
+                    NumberType::Binary => ("0b", format!("{:b}", abs)),
+                    NumberType::LowerExp => ("", format!("{:e}", abs)),
+                    NumberType::UpperExp => ("", format!("{:E}", abs)),
+                    NumberType::Pointer => return Err(unsupported_number_type(context, stringify!($ty))),
+                };
+                context.pad_integral(is_nonnegative, prefix, &localize(context, digits), out)?;
+                Ok(())
+            }
+
+            fn curly_is_truthy(&self) -> CurlyResult<bool> {
+                Ok(*self != 0)
+            }
+        }
+    };
+}
+
+integer_impls!(unsigned: u8, u16, u32, u64, u128, usize);
+integer_impls!(signed: i8, i16, i32, i64, i128, isize);
+
+macro_rules! float_impls {
+    ($($ty:ty),*) => {
+        $(
+            impl CurlyFmt for $ty {
+                fn curly_fmt(&self, context: &CurlyContext) -> CurlyFmtResult {
+                    let mut formatted = String::new();
+                    self.curly_write(context, &mut formatted)?;
+                    Ok(formatted)
+                }
+
+                fn curly_write(&self, context: &CurlyContext, out: &mut dyn std::fmt::Write) -> CurlyResult<()> {
+                    let is_nonnegative = self.is_nan() || self.is_sign_positive();
+                    let abs = self.abs();
+                    let digits = match (context.flags.number_type, context.flags.precision) {
+                        (NumberType::Normal, None) => abs.to_string(),
+                        (NumberType::Normal, Some(precision)) => format!("{:.*}", precision, abs),
+                        (NumberType::LowerExp, None) => format!("{:e}", abs),
+                        (NumberType::LowerExp, Some(precision)) => format!("{:.*e}", precision, abs),
+                        (NumberType::UpperExp, None) => format!("{:E}", abs),
+                        (NumberType::UpperExp, Some(precision)) => format!("{:.*E}", precision, abs),
+                        _ => return Err(unsupported_number_type(context, stringify!($ty))),
+                    };
+                    context.pad_integral(is_nonnegative, "", &localize(context, digits), out)?;
+                    Ok(())
+                }
+
+                #[allow(clippy::float_cmp)]
+                fn curly_is_truthy(&self) -> CurlyResult<bool> {
+                    Ok(*self != 0.0)
+                }
+            }
+        )*
+    };
+}
+
+float_impls!(f32, f64);
+
+/// Formats the value if there is one. `None` is formatted as the `@none` custom flag,
+/// or as an empty string without it (`{nickname:>8 @none=n/a}`).
+impl<T: CurlyFmt> CurlyFmt for Option<T> {
+    fn curly_fmt(&self, context: &CurlyContext) -> CurlyFmtResult {
+        let mut formatted = String::new();
+        self.curly_write(context, &mut formatted)?;
+        Ok(formatted)
+    }
+
+    fn curly_write(
+        &self,
+        context: &CurlyContext,
+        out: &mut dyn std::fmt::Write,
+    ) -> CurlyResult<()> {
+        if let Some(value) = self {
+            return value.curly_write(context, out);
+        }
+
+        let none = context.custom::<String>("none")?.unwrap_or_default();
+        context.pad_to(&none, Alignment::Left, out)?;
+        Ok(())
+    }
+
+    fn is_safe(&self) -> bool {
+        self.as_ref().is_some_and(CurlyFmt::is_safe)
+    }
+
+    /// `None` is falsy, and `Some` has the truthiness of its value.
+    fn curly_is_truthy(&self) -> CurlyResult<bool> {
+        self.as_ref().map_or(Ok(false), CurlyFmt::curly_is_truthy)
+    }
+}
+
+/// Formats the `Ok` value, or fails with the `Err` value as a
+/// [`CurlyErrorKind::Generic`](CurlyErrorKind::Generic) error.
+impl<T: CurlyFmt, E: std::fmt::Display> CurlyFmt for Result<T, E> {
+    fn curly_fmt(&self, context: &CurlyContext) -> CurlyFmtResult {
+        match self {
+            Ok(value) => value.curly_fmt(context),
+            Err(error) => Err(result_error(error)),
+        }
+    }
+
+    fn curly_write(
+        &self,
+        context: &CurlyContext,
+        out: &mut dyn std::fmt::Write,
+    ) -> CurlyResult<()> {
+        match self {
+            Ok(value) => value.curly_write(context, out),
+            Err(error) => Err(result_error(error)),
+        }
+    }
+
+    fn is_safe(&self) -> bool {
+        self.as_ref().is_ok_and(CurlyFmt::is_safe)
+    }
+
+    /// `Err` is falsy, and `Ok` has the truthiness of its value.
+    fn curly_is_truthy(&self) -> CurlyResult<bool> {
+        self.as_ref().map_or(Ok(false), CurlyFmt::curly_is_truthy)
+    }
+}
+
+fn result_error(error: &dyn std::fmt::Display) -> CurlyErrorKind {
+    CurlyErrorKind::Generic(CurlyError::from_boxed(error.to_string()))
+}
+
+/// Write `items`, each formatted with `context`, separated by the `@sep` custom flag (`, ` by default).
+///
+/// The separator before the last item is the `@last` custom flag, if it's set, for natural-language
+/// lists such as `a, b and c` (`{tags:@last=" and "}`).
+fn join<'a, T: CurlyFmt + ?Sized + 'a>(
+    items: impl ExactSizeIterator<Item = &'a T>,
+    context: &CurlyContext,
+    out: &mut dyn std::fmt::Write,
+) -> CurlyResult<()> {
+    let separator = context.custom::<String>("sep")?;
+    let separator = separator.as_deref().unwrap_or(", ");
+    let last = context.custom::<String>("last")?;
+    let last = last.as_deref().unwrap_or(separator);
+
+    let len = items.len();
+    for (index, item) in items.enumerate() {
+        if index + 1 == len && index > 0 {
+            out.write_str(last)?;
+        } else if index > 0 {
+            out.write_str(separator)?;
+        }
+        item.curly_write(context, out)?;
+    }
+    Ok(())
+}
+
+/// Implements `CurlyFmt` for collections, joining their items (or the values of maps) with [`join`](join).
+macro_rules! collection_impls {
+    ($($(#[$attr:meta])* <$($generic:ident $(: $bound:path)?),*> $ty:ty => $iter:ident),*) => {
+        $(
+            $(#[$attr])*
+            impl<$($generic $(: $bound)?),*> CurlyFmt for $ty {
+                fn curly_fmt(&self, context: &CurlyContext) -> CurlyFmtResult {
+                    let mut formatted = String::new();
+                    self.curly_write(context, &mut formatted)?;
+                    Ok(formatted)
+                }
+
+                fn curly_write(&self, context: &CurlyContext, out: &mut dyn std::fmt::Write) -> CurlyResult<()> {
+                    join(self.$iter(), context, out)
+                }
+
+                fn is_safe(&self) -> bool {
+                    self.$iter().all(CurlyFmt::is_safe)
+                }
+
+                fn curly_is_truthy(&self) -> CurlyResult<bool> {
+                    Ok(self.$iter().next().is_some())
+                }
+            }
+        )*
+    };
+}
+
+collection_impls!(
+    <T: CurlyFmt> [T] => iter,
+    /// Formats the items separated by the `@sep` custom flag (`, ` by default), or by the
+    /// `@last` custom flag before the last item if it's set. Other collections, and the
+    /// values of maps, are formatted the same way.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use curly::Template;
+    ///
+    /// let tags = vec!["a", "b", "c"];
+    /// let values: &[(&str, &dyn curly::formatting::CurlyFmt)] = &[("tags", &tags)];
+    /// let template = Template::parse(r#"{tags:@last=" and "} ({tags:@sep=/})"#).unwrap();
+    /// assert_eq!(template.render(&values).unwrap(), "a, b and c (a/b/c)");
+    /// ```
+    <T: CurlyFmt> Vec<T> => iter,
+    <T: CurlyFmt> VecDeque<T> => iter,
+    <T: CurlyFmt, S> HashSet<T, S> => iter,
+    <T: CurlyFmt> BTreeSet<T> => iter,
+    <K, V: CurlyFmt, S> HashMap<K, V, S> => values,
+    <K, V: CurlyFmt> BTreeMap<K, V> => values
+);
+
+/// Implements `CurlyFmt` for references and smart pointers, forwarding to the pointee.
+macro_rules! forwarding_impls {
+    ($(<$($lifetime:lifetime,)? T $(: $bound:path)?> $ty:ty),*) => {
+        $(
+            impl<$($lifetime,)? T: CurlyFmt + ?Sized $(+ $bound)?> CurlyFmt for $ty {
+                fn curly_fmt(&self, context: &CurlyContext) -> CurlyFmtResult {
+                    (**self).curly_fmt(context)
+                }
+
+                fn curly_write(&self, context: &CurlyContext, out: &mut dyn std::fmt::Write) -> CurlyResult<()> {
+                    (**self).curly_write(context, out)
+                }
+
+                fn is_safe(&self) -> bool {
+                    (**self).is_safe()
+                }
+
+                fn curly_is_truthy(&self) -> CurlyResult<bool> {
+                    (**self).curly_is_truthy()
+                }
+            }
+        )*
+    };
+}
+
+forwarding_impls!(
+    <'a, T> &'a T,
+    <'a, T> &'a mut T,
+    <T> Box<T>,
+    <T> Rc<T>,
+    <T> Arc<T>,
+    <'a, T: ToOwned> Cow<'a, T>
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::locale::Locale;
+    use crate::prelude::*;
+
+    fn context(segment: &str) -> CurlyContext {
+        CurlyContext::from_segment(segment, 1, 1).unwrap()
+    }
+
+    #[test]
+    fn integers() {
+        assert_eq!(42u8.curly_fmt(&context("x")).unwrap(), "42");
+        assert_eq!((-42i32).curly_fmt(&context("x:>6")).unwrap(), "   -42");
+        assert_eq!(42i64.curly_fmt(&context("x:+06")).unwrap(), "+00042");
+        assert_eq!(255u32.curly_fmt(&context("x:#x")).unwrap(), "0xff");
+        assert_eq!(5usize.curly_fmt(&context("x:#010b")).unwrap(), "0b00000101");
+        assert_eq!(i8::MIN.curly_fmt(&context("x:<6")).unwrap(), "-128  ");
+        assert!(1u8.curly_fmt(&context("x:p")).is_err());
+    }
+
+    #[test]
+    fn floats() {
+        assert_eq!(1.5f64.curly_fmt(&context("x")).unwrap(), "1.5");
+        assert_eq!(1.23456f32.curly_fmt(&context("x:.2")).unwrap(), "1.23");
+        assert_eq!((-2.5f64).curly_fmt(&context("x:08.2")).unwrap(), "-0002.50");
+        assert_eq!(1234.5f64.curly_fmt(&context("x:e")).unwrap(), "1.2345e3");
+        assert!(1.0f64.curly_fmt(&context("x:x")).is_err());
+    }
+
+    #[test]
+    fn localized_numbers() {
+        let mut german = context("x:,.2");
+        german.locale = Locale::from_tag("de").unwrap();
+        assert_eq!(1_234_567.891f64.curly_fmt(&german).unwrap(), "1.234.567,89");
+        assert_eq!((-1234i32).curly_fmt(&german).unwrap(), "-1.234");
+
+        let mut french = context("x:>12");
+        french.locale = Locale::from_tag("fr").unwrap();
+        assert_eq!(0.5f32.curly_fmt(&french).unwrap(), "         0,5");
+        assert_eq!(2.5e3f64.curly_fmt(&context("x:e")).unwrap(), "2.5e3");
+
+        assert_eq!(
+            1_234_567u32.curly_fmt(&context("x:,")).unwrap(),
+            "1,234,567"
+        );
+        assert_eq!(
+            1_234_567u32.curly_fmt(&context("x:_")).unwrap(),
+            "1_234_567"
+        );
+        assert_eq!(999u32.curly_fmt(&context("x:,")).unwrap(), "999");
+        assert_eq!(
+            0xdead_beefu32.curly_fmt(&context("x:#_x")).unwrap(),
+            "0xdead_beef"
+        );
+        assert_eq!(f64::NAN.curly_fmt(&context("x:,")).unwrap(), "NaN");
+
+        let mut indian = context("x:,");
+        indian.locale = Locale::from_tag("en-IN").unwrap();
+        assert_eq!(12_345_678u64.curly_fmt(&indian).unwrap(), "1,23,45,678");
+    }
+
+    #[test]
+    fn strings() {
+        assert_eq!("abc".curly_fmt(&context("x:*^7")).unwrap(), "**abc**");
+        assert_eq!("abcdef".curly_fmt(&context("x:.3")).unwrap(), "abc");
+    }
+
+    #[test]
+    fn bools_chars_and_unit() {
+        assert_eq!(true.curly_fmt(&context("x:>6")).unwrap(), "  true");
+        assert_eq!(false.curly_fmt(&context("x:yes/no")).unwrap(), "no");
+        assert_eq!(true.curly_fmt(&context("x:-<3✓/✗")).unwrap(), "✓--");
+        assert_eq!(false.curly_fmt(&context("x:/off")).unwrap(), "off");
+        assert_eq!(true.curly_fmt(&context("x:1/0")).unwrap(), "1");
+        assert_eq!(false.curly_fmt(&context("x:1/0")).unwrap(), "0");
+        assert_eq!(true.curly_fmt(&context("x:+/-")).unwrap(), "+");
+        assert_eq!(false.curly_fmt(&context("x:0/1")).unwrap(), "1");
+        assert_eq!(true.curly_fmt(&context("x:>21/0")).unwrap(), " 1");
+        assert_eq!(
+            true.curly_fmt(&context("x:yes")).unwrap_err().to_string(),
+            "Error: Invalid pattern `yes` for `bool`, expected `true/false` renderings such as `yes/no`"
+        );
+
+        assert_eq!('é'.curly_fmt(&context("x:*^5")).unwrap(), "**é**");
+        assert_eq!('x'.curly_fmt(&context("x:>3")).unwrap(), "  x");
+        assert_eq!(().curly_fmt(&context("x:.>3")).unwrap(), "...");
+    }
+
+    #[test]
+    fn forwarding() {
+        let shared: std::sync::Arc<str> = "abc".into();
+        assert_eq!(shared.curly_fmt(&context("x:>4")).unwrap(), " abc");
+        assert_eq!(Box::new(7u8).curly_fmt(&context("x:03")).unwrap(), "007");
+        let borrowed: std::borrow::Cow<'_, str> = "de".into();
+        assert_eq!((&&borrowed).curly_fmt(&context("x:-<3")).unwrap(), "de-");
+        assert!(std::rc::Rc::new(true).is_truthy());
+    }
+
+    #[test]
+    fn options_and_results() {
+        assert_eq!(Some(5u8).curly_fmt(&context("x:>3")).unwrap(), "  5");
+        assert_eq!(None::<u8>.curly_fmt(&context("x:>3")).unwrap(), "   ");
+        assert_eq!(
+            None::<u8>.curly_fmt(&context("x:>5 @none=n/a")).unwrap(),
+            "  n/a"
+        );
+
+        let ok: Result<&str, String> = Ok("yes");
+        assert_eq!(ok.curly_fmt(&context("x:^5")).unwrap(), " yes ");
+        let err: Result<&str, String> = Err(String::from("lookup failed"));
+        assert_eq!(
+            err.curly_fmt(&context("x")).unwrap_err().to_string(),
+            "Error: lookup failed"
+        );
+        assert!(ok.is_truthy() && !err.is_truthy());
+    }
+
+    #[test]
+    fn collections() {
+        let tags = vec!["a", "b", "c"];
+        assert_eq!(tags.curly_fmt(&context("x")).unwrap(), "a, b, c");
+        assert_eq!(
+            tags.curly_fmt(&context(r#"x:>2 @sep=" | ""#)).unwrap(),
+            " a |  b |  c"
+        );
+        assert_eq!(
+            tags.curly_fmt(&context(r#"x:@last=" and ""#)).unwrap(),
+            "a, b and c"
+        );
+        assert_eq!(
+            tags[..1].curly_fmt(&context(r#"x:@last=" and ""#)).unwrap(),
+            "a"
+        );
+        assert_eq!(Vec::<u8>::new().curly_fmt(&context("x")).unwrap(), "");
+
+        let set: std::collections::BTreeSet<_> = vec![3u8, 1, 2].into_iter().collect();
+        assert_eq!(set.curly_fmt(&context("x:02 @sep=/")).unwrap(), "01/02/03");
+
+        let map: std::collections::BTreeMap<_, _> =
+            vec![("b", 2.5f64), ("a", 1.0)].into_iter().collect();
+        assert_eq!(map.curly_fmt(&context("x:.1")).unwrap(), "1.0, 2.5");
+    }
+
+    #[test]
+    fn truthiness() {
+        assert!(true.is_truthy() && !false.is_truthy());
+        assert!(1u8.is_truthy() && !0i64.is_truthy());
+        assert!((-0.5f32).is_truthy() && !0.0f64.is_truthy());
+        assert!("a".is_truthy() && !String::new().is_truthy());
+        assert!(Some(1).is_truthy() && !Some(0).is_truthy() && !None::<u8>.is_truthy());
+        assert!(vec![false].is_truthy() && !Vec::<u8>::new().is_truthy());
+        assert!('a'.is_truthy() && !().is_truthy());
+        assert!(!Err::<u8, _>("failed").is_truthy());
+    }
+
+    #[test]
+    fn custom_flags() {
+        let context = context("x:%H:%M @places=two @short");
+        assert_eq!(context.custom_pattern(), Some("%H:%M"));
+        assert!(context.has_custom("short") && !context.has_custom("long"));
+        assert_eq!(
+            context.custom::<u8>("places").unwrap_err().to_string(),
+            "Error: Invalid value `two` for custom flag `places`: invalid digit found in string"
+        );
+        assert!(context.custom::<String>("short").is_err());
     }
 }
@@ -1,6 +1,9 @@
 use super::prelude::*;
+use crate::locale::Locale;
 use std::fmt::Debug;
 
+pub use curly_parser::flags::{Alignment, CurlyFlags, CustomFlags, Grouping, NumberType, Sign};
+
 /// The formatting context for a single format segment.
 /// This includes things such as custom flags, default flags, and
 /// the actual specifier.
@@ -9,103 +12,200 @@ pub struct CurlyContext {
     /// Custom flags (remaining flags that are unparsed by Curly
     /// so that they can be used for custom formatting behaviours.
     pub custom_flags: Option<String>,
+    /// The custom flags, parsed into a pattern and named flags (`%Y-%m-%d @tz=UTC`).
+    ///
+    /// Use [`custom`](CurlyContext::custom) to get the typed value of a named flag.
+    pub custom: CustomFlags,
     /// Flags that are parsed by Curly for things such as padding,
     /// format type, etc...
     pub flags: CurlyFlags,
     /// The specifier for this format segment.
     pub specifier: Option<String>,
-}
-
-/// The number display type for a single format segment.
-#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
-pub enum NumberType {
-    /// Octal (`o`) ⇒ [`Octal`](std::fmt::Octal)
-    Octal,
-    /// Lowercase hexadecimal (`x`) ⇒ [`LowerHex`](std::fmt::LowerHex)
-    LowerHex,
-    /// Uppercase hexadecimal (`X`) ⇒ [`UpperHex`](std::fmt::UpperHex)
-    UpperHex,
-    /// Show as location in memory, usually as hex (`p`) ⇒ [`Pointer`](std::fmt::Pointer)
-    Pointer,
-    /// Binary (`b`) ⇒ [`Binary`](std::fmt::Binary)
-    Binary,
-    /// Lowercase scientific notation (`e`) ⇒ [`LowerExp`](std::fmt::LowerExp)
-    LowerExp,
-    /// Uppercase scientific notation (`E`) ⇒ [`UpperExp`](std::fmt::UpperExp)
-    UpperExp,
-    /// Normal number types
-    Normal,
-}
-
-/// The sign for a single format segment (`+` or `-`). `-` is currently unimplemented, but it may be used in the future.
-#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
-pub enum Sign {
-    /// Always print sign
-    Plus,
-    /// Unused, but may be used in the future with [`format!`](std::format!), so it's reserved here.
-    Minus,
-}
-
-/// The padding alignment for a single format segment.
-#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
-pub enum Alignment {
-    /// Left-aligned padding (`<`)
-    Left,
-    /// Right-aligned padding (`>`)
-    Right,
-    /// Center-aligned padding (`^`)
-    Center,
-}
-
-/// Flags for a single format segment, e.g. padding,
-/// precision, number type, etc...
-#[derive(Debug, Eq, PartialEq, Clone, Hash)]
-pub struct CurlyFlags {
-    /// The padding fill character (default: `' '`)
-    pub fill: char,
-    /// The padding alignment
-    pub align: Option<Alignment>,
-    /// The padding width
-    pub width: Option<usize>,
-    /// The float precision
-    pub precision: Option<usize>,
-    /// The decimal sign
-    pub sign: Option<Sign>,
-    /// Whether the zero (`0`) flag is set or not
-    pub sign_aware_zero_pad: bool,
-    /// Whether the alternate (`#`) flag is set or not
-    pub alternate: bool,
-    /// The type of number (Normal, Octal, Lower/Upper Hex, etc...)
-    pub number_type: NumberType,
+    /// The locale to format numbers with (see [`Template::with_locale`](crate::Template::with_locale)).
+    pub locale: Locale,
 }
 
 impl CurlyContext {
     /// Generate a `CurlyContext` from a single format segment (one statement between `{}`s)
     ///
-    /// This function does not validate input YET, as that is done by // TODO: input parsing
+    /// `base_row` and `base_col` are the position of the segment within its format string,
+    /// and are only used for error reporting.
+    ///
     /// # Errors
     ///
-    /// TODO: Once we get this actually working
+    /// [`CurlyErrorKind::Syntax`](CurlyErrorKind::Syntax) if the segment is malformed.
     pub fn from_segment(
-        _format_segment: &str,
-        _base_row: usize,
-        _base_col: usize,
+        format_segment: &str,
+        base_row: usize,
+        base_col: usize,
     ) -> CurlyResult<Self> {
-        unimplemented!();
+        let placeholder = curly_parser::parse_segment(format_segment, base_row, base_col)?;
+        Ok(Self::from(placeholder))
+    }
+
+    /// Get the value of the named custom flag `name` (`@name=value`), parsed as a `T`.
+    ///
+    /// Returns `Ok(None)` if the flag isn't set.
+    ///
+    /// # Errors
+    ///
+    /// [`CurlyErrorKind::Generic`](CurlyErrorKind::Generic) if the flag doesn't have a value,
+    /// or its value can't be parsed as a `T`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use curly::formatting::CurlyContext;
+    ///
+    /// let context = CurlyContext::from_segment("n:@currency=EUR @places=2", 1, 1).unwrap();
+    /// assert_eq!(context.custom::<String>("currency").unwrap().as_deref(), Some("EUR"));
+    /// assert_eq!(context.custom::<u8>("places").unwrap(), Some(2));
+    /// assert_eq!(context.custom::<u8>("round").unwrap(), None);
+    /// ```
+    pub fn custom<T>(&self, name: &str) -> CurlyResult<Option<T>>
+    where
+        T: std::str::FromStr,
+        T::Err: std::fmt::Display,
+    {
+        let value = match self.custom.get(name) {
+            None => return Ok(None),
+            Some(None) => {
+                return Err(CurlyErrorKind::Generic(CurlyError::from_boxed(format!(
+                    "Custom flag `{name}` requires a value (`@{name}=value`)"
+                ))))
+            }
+            Some(Some(value)) => value,
+        };
+
+        value.parse().map(Some).map_err(|error| {
+            CurlyErrorKind::Generic(CurlyError::from_boxed(format!(
+                "Invalid value `{value}` for custom flag `{name}`: {error}"
+            )))
+        })
+    }
+
+    /// Whether the named custom flag `name` is set, e.g. a switch like `@short`.
+    pub fn has_custom(&self, name: &str) -> bool {
+        self.custom.contains(name)
+    }
+
+    /// The free-form pattern of the custom flags, e.g. `%Y-%m-%d` in `{date:%Y-%m-%d}`.
+    pub fn custom_pattern(&self) -> Option<&str> {
+        self.custom.pattern.as_deref()
+    }
+
+    /// Pad `text` according to the fill, alignment and width flags, truncating it to the
+    /// precision first if there is one (like [`Formatter::pad`](std::fmt::Formatter::pad)).
+    ///
+    /// `default_align` is used if no alignment was specified, e.g. numbers are right-aligned.
+    pub fn pad(&self, text: &str, default_align: Alignment) -> String {
+        let mut padded = String::with_capacity(text.len());
+        // Writing to a `String` never fails.
+        let _ = self.pad_to(text, default_align, &mut padded);
+        padded
+    }
+
+    /// Like [`pad`](CurlyContext::pad), but writes the padded text to `out`.
+    ///
+    /// # Errors
+    ///
+    /// Any error returned by `out`.
+    pub fn pad_to(
+        &self,
+        text: &str,
+        default_align: Alignment,
+        out: &mut dyn std::fmt::Write,
+    ) -> std::fmt::Result {
+        let text = match self.flags.precision {
+            Some(precision) => match text.char_indices().nth(precision) {
+                Some((idx, _)) => &text[..idx],
+                None => text,
+            },
+            None => text,
+        };
+
+        let len = text.chars().count();
+        let width = match self.flags.width {
+            Some(width) if width > len => width,
+            _ => return out.write_str(text),
+        };
+
+        let padding = width - len;
+        let (before, after) = match self.flags.align.unwrap_or(default_align) {
+            Alignment::Left => (0, padding),
+            Alignment::Right => (padding, 0),
+            Alignment::Center => (padding / 2, padding - padding / 2),
+        };
+
+        for _ in 0..before {
+            out.write_char(self.flags.fill)?;
+        }
+        out.write_str(text)?;
+        for _ in 0..after {
+            out.write_char(self.flags.fill)?;
+        }
+        Ok(())
+    }
+
+    /// Write a number to `out`, applying the sign, alternate prefix, zero padding and
+    /// padding flags (like [`Formatter::pad_integral`](std::fmt::Formatter::pad_integral)).
+    ///
+    /// `digits` should not contain a sign, and `prefix` is only written with the alternate (`#`) flag.
+    ///
+    /// # Errors
+    ///
+    /// Any error returned by `out`.
+    pub fn pad_integral(
+        &self,
+        is_nonnegative: bool,
+        prefix: &str,
+        digits: &str,
+        out: &mut dyn std::fmt::Write,
+    ) -> std::fmt::Result {
+        let sign = if !is_nonnegative {
+            "-"
+        } else if self.flags.sign == Some(Sign::Plus) {
+            "+"
+        } else {
+            ""
+        };
+        let prefix = if self.flags.alternate { prefix } else { "" };
+
+        if self.flags.sign_aware_zero_pad {
+            let len = sign.len() + prefix.len() + digits.chars().count();
+            out.write_str(sign)?;
+            out.write_str(prefix)?;
+            for _ in len..self.flags.width.unwrap_or(0) {
+                out.write_char('0')?;
+            }
+            return out.write_str(digits);
+        }
+
+        let mut number = String::with_capacity(sign.len() + prefix.len() + digits.len());
+        number.push_str(sign);
+        number.push_str(prefix);
+        number.push_str(digits);
+
+        // The precision of a number isn't a maximum width like it is for strings.
+        let context = CurlyContext {
+            flags: CurlyFlags {
+                precision: None,
+                ..self.flags.clone()
+            },
+            ..CurlyContext::default()
+        };
+        context.pad_to(&number, Alignment::Right, out)
     }
 }
 
-impl Default for CurlyFlags {
-    fn default() -> Self {
+impl From<curly_parser::Placeholder> for CurlyContext {
+    fn from(placeholder: curly_parser::Placeholder) -> Self {
         Self {
-            fill: ' ',
-            align: None,
-            width: None,
-            precision: None,
-            sign: None,
-            sign_aware_zero_pad: false,
-            alternate: false,
-            number_type: NumberType::Normal,
+            custom_flags: placeholder.custom_flags,
+            custom: placeholder.custom,
+            flags: placeholder.flags,
+            specifier: Some(placeholder.key),
+            locale: Locale::default(),
         }
     }
 }
@@ -119,4 +219,59 @@ pub trait CurlyFmt {
     /// There can be any error that is returned with the formatting of a single format segment.
     /// These will be passed up to the end result of the `curly!` or derivative macros.
     fn curly_fmt(&self, context: &CurlyContext) -> CurlyFmtResult;
+
+    /// Formats `&self` directly into `out` using the `CurlyContext` as a reference.
+    ///
+    /// By default, this writes the result of [`curly_fmt`](CurlyFmt::curly_fmt). Implement this
+    /// to avoid allocating an intermediate `String` for every format segment.
+    ///
+    /// # Errors
+    ///
+    /// Any error from formatting, or [`CurlyErrorKind::Write`](CurlyErrorKind::Write)
+    /// if writing to `out` failed.
+    fn curly_write(
+        &self,
+        context: &CurlyContext,
+        out: &mut dyn std::fmt::Write,
+    ) -> CurlyResult<()> {
+        out.write_str(&self.curly_fmt(context)?)?;
+        Ok(())
+    }
+
+    /// Whether the output of `&self` is already safe for the output format of a template,
+    /// so it isn't escaped (see [`escaping`](crate::escaping)). This is `false` by default.
+    fn is_safe(&self) -> bool {
+        false
+    }
+
+    /// Whether `&self` is truthy in conditional sections (`{#if key}...{/if}`), e.g. `true`,
+    /// a non-zero number, a non-empty string or a non-empty collection.
+    ///
+    /// By default, this is whether `&self` formats to a non-empty string. This is the only
+    /// definition of truthiness, which [`Truthy`](Truthy) and providers use.
+    ///
+    /// # Errors
+    ///
+    /// Any error from formatting `&self`.
+    fn curly_is_truthy(&self) -> CurlyResult<bool> {
+        Ok(!self.curly_fmt(&CurlyContext::default())?.is_empty())
+    }
+}
+
+/// The truthiness of a value, used by conditional sections (`{#if key}...{/if}`).
+///
+/// This is implemented for every [`CurlyFmt`](CurlyFmt) type with
+/// [`CurlyFmt::curly_is_truthy`](CurlyFmt::curly_is_truthy), so implement that to change
+/// the truthiness of a type.
+pub trait Truthy {
+    /// Whether `&self` is truthy, e.g. `true`, a non-zero number or a non-empty string.
+    ///
+    /// Values that fail to format aren't truthy.
+    fn is_truthy(&self) -> bool;
+}
+
+impl<T: CurlyFmt + ?Sized> Truthy for T {
+    fn is_truthy(&self) -> bool {
+        self.curly_is_truthy().unwrap_or(false)
+    }
 }
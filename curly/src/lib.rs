@@ -1,12 +1,29 @@
 #![warn(clippy::pedantic)]
 #![allow(clippy::must_use_candidate)]
 
+pub mod catalog;
+pub mod combinators;
+#[cfg(any(feature = "chrono", feature = "time"))]
+pub mod datetime;
+#[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+pub mod documents;
 pub mod errors;
+pub mod escaping;
+pub mod filters;
 pub mod formatters;
 pub mod formatting;
+pub mod limits;
+pub mod locale;
 pub mod parsing;
+pub mod providers;
+#[cfg(feature = "serde")]
+pub mod serialize;
+pub mod template;
+pub mod units;
 
+pub use catalog::Catalog;
 pub use errors::*;
+pub use template::Template;
 
 /// Re-export Provider derivation with `#[cfg(feature = "derive")]`
 #[cfg(feature = "derive")]
@@ -29,64 +46,278 @@ mod internal_macros {
     }
 }
 
-// TODO: actual formatting
+/// Format a runtime format string using the values provided by a [`Provider`](Provider).
+///
+/// Arguments are either named (`name: Type = value`), positional (`value`, used by `{}` and `{0}`),
+/// or a delegate provider (`..delegate: Type`) for any other keys, which must be the last argument.
+///
+/// When the format string is a string literal, it is parsed at compile time, and every
+/// format segment is checked against the named arguments and the delegate provider's
+/// [`ProviderSchema`](ProviderSchema) (implemented by `#[derive(Provider)]`). The keys of
+/// delegate providers without a schema, such as maps, are only checked when formatting.
+/// Otherwise, the format string is parsed when the macro is evaluated.
+///
+/// In conditional sections (`{#if key}`), arguments are truthy according to
+/// [`CurlyFmt::curly_is_truthy`](formatting::CurlyFmt::curly_is_truthy).
+///
+/// # Example
+///
+/// ```rust
+/// # #[macro_use] extern crate curly;
+/// # fn main() {
+/// let format_string = String::from("Hello, {name}!");
+/// let formatted = curly!(format_string, name: String = String::from("world"));
+/// assert_eq!(formatted.unwrap(), "Hello, world!");
+///
+/// let formatted = curly!("{} {0} {}", String::from("a"), String::from("b"));
+/// assert_eq!(formatted.unwrap(), "a a b");
+///
+/// let mut map = std::collections::HashMap::new();
+/// map.insert(String::from("name"), String::from("map"));
+/// let formatted = curly!("{greeting}, {name}!", greeting: String = String::from("Hello"), ..map: std::collections::HashMap<String, String>);
+/// assert_eq!(formatted.unwrap(), "Hello, map!");
+/// # }
+/// ```
 #[macro_export]
 macro_rules! curly {
-    ($format_string:expr, $($argument_name:ident: $argument_type:ty = $argument_value:expr), *, ..$delegate_provider:ident: $delegate_type:ty) => {{
+    ($format_string:literal $(, $($arguments:tt)*)?) => {{
+        $crate::__curly_literal!($crate, $format_string $(, $($arguments)*)?);
+        $crate::format($format_string, &$crate::__curly_provider!($($($arguments)*)?))
+    }};
+    ($format_string:expr $(, $($arguments:tt)*)?) => {
+        $crate::format(
+            ::std::convert::AsRef::<str>::as_ref(&$format_string),
+            &$crate::__curly_provider!($($($arguments)*)?),
+        )
+    };
+}
+
+/// Write a runtime format string to a [`fmt::Write`](std::fmt::Write) or [`io::Write`](std::io::Write),
+/// like [`write!`](std::write!).
+///
+/// This accepts the same arguments as [`curly!`](curly!) after the destination, and returns
+/// a [`CurlyResult<()>`](CurlyResult). As with `write!`, the destination's `Write` trait must be in scope.
+///
+/// The whole format string is formatted before anything is written, so nothing is written
+/// if formatting fails. Use [`Template::render_to`](Template::render_to) to stream large outputs.
+///
+/// # Example
+///
+/// ```rust
+/// # #[macro_use] extern crate curly;
+/// # fn main() {
+/// use std::fmt::Write;
+///
+/// let mut out = String::new();
+/// curly_write!(out, "{a} and {b}", a: String = String::from("this"), b: String = String::from("that")).unwrap();
+/// assert_eq!(out, "this and that");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! curly_write {
+    ($destination:expr, $format_string:literal $(, $($arguments:tt)*)?) => {{
+        $crate::__curly_literal!($crate, $format_string $(, $($arguments)*)?);
+        $crate::__curly_write!($destination, "", $format_string, $crate::__curly_provider!($($($arguments)*)?))
+    }};
+    ($destination:expr, $format_string:expr $(, $($arguments:tt)*)?) => {
+        $crate::__curly_write!($destination, "", $format_string, $crate::__curly_provider!($($($arguments)*)?))
+    };
+}
+
+/// Write a runtime format string to a [`fmt::Write`](std::fmt::Write) or [`io::Write`](std::io::Write),
+/// followed by a newline, like [`writeln!`](std::writeln!).
+///
+/// See [`curly_write!`](curly_write!).
+#[macro_export]
+macro_rules! curly_writeln {
+    ($destination:expr, $format_string:literal $(, $($arguments:tt)*)?) => {{
+        $crate::__curly_literal!($crate, $format_string $(, $($arguments)*)?);
+        $crate::__curly_write!($destination, "\n", $format_string, $crate::__curly_provider!($($($arguments)*)?))
+    }};
+    ($destination:expr, $format_string:expr $(, $($arguments:tt)*)?) => {
+        $crate::__curly_write!($destination, "\n", $format_string, $crate::__curly_provider!($($($arguments)*)?))
+    };
+}
+
+/// Print a runtime format string to the standard output, like [`print!`](std::print!).
+///
+/// This accepts the same arguments as [`curly!`](curly!), and returns a [`CurlyResult<()>`](CurlyResult)
+/// instead of panicking if the format string is invalid or writing fails.
+#[macro_export]
+macro_rules! curly_print {
+    ($($arguments:tt)*) => {{
+        use ::std::io::Write as _;
+        $crate::curly_write!(::std::io::stdout().lock(), $($arguments)*)
+    }};
+}
+
+/// Print a runtime format string to the standard output, followed by a newline, like [`println!`](std::println!).
+///
+/// See [`curly_print!`](curly_print!).
+#[macro_export]
+macro_rules! curly_println {
+    ($($arguments:tt)*) => {{
+        use ::std::io::Write as _;
+        $crate::curly_writeln!(::std::io::stdout().lock(), $($arguments)*)
+    }};
+}
+
+/// Print a runtime format string to the standard error, like [`eprint!`](std::eprint!).
+///
+/// See [`curly_print!`](curly_print!).
+#[macro_export]
+macro_rules! curly_eprint {
+    ($($arguments:tt)*) => {{
+        use ::std::io::Write as _;
+        $crate::curly_write!(::std::io::stderr().lock(), $($arguments)*)
+    }};
+}
+
+/// Print a runtime format string to the standard error, followed by a newline, like [`eprintln!`](std::eprintln!).
+///
+/// See [`curly_print!`](curly_print!).
+#[macro_export]
+macro_rules! curly_eprintln {
+    ($($arguments:tt)*) => {{
+        use ::std::io::Write as _;
+        $crate::curly_writeln!(::std::io::stderr().lock(), $($arguments)*)
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __curly_write {
+    ($destination:expr, $suffix:literal, $format_string:expr, $provider:expr) => {
+        match $crate::format(
+            ::std::convert::AsRef::<str>::as_ref(&$format_string),
+            &$provider,
+        ) {
+            ::std::result::Result::Ok(formatted) => $destination
+                .write_fmt(::std::format_args!(
+                    ::std::concat!("{}", $suffix),
+                    formatted
+                ))
+                .map_err(::std::convert::Into::<$crate::CurlyErrorKind>::into),
+            ::std::result::Result::Err(error) => ::std::result::Result::Err(error),
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __curly_provider {
+    (@munch [$($positional:tt)*] [$($named:tt)*] [$($delegate:tt)*] $argument_name:ident: $argument_type:ty = $argument_value:expr $(, $($rest:tt)*)?) => {
+        $crate::__curly_provider!(@munch [$($positional)*] [$($named)* ($argument_name: $argument_type = $argument_value)] [$($delegate)*] $($($rest)*)?)
+    };
+    (@munch [$($positional:tt)*] [$($named:tt)*] [] ..$delegate_provider:ident: $delegate_type:ty $(,)?) => {
+        $crate::__curly_provider!(@munch [$($positional)*] [$($named)*] [$delegate_provider: $delegate_type])
+    };
+    (@munch [$($positional:tt)*] [$($named:tt)*] [] $positional_value:expr $(, $($rest:tt)*)?) => {
+        $crate::__curly_provider!(@munch [$($positional)* ($positional_value)] [$($named)*] [] $($($rest)*)?)
+    };
+    (@munch [$(($positional_value:expr))*] [$(($argument_name:ident: $argument_type:ty = $argument_value:expr))*] [$($delegate_provider:ident: $delegate_type:ty)?]) => {{
+        #[allow(unused_imports)]
         use $crate::formatting::CurlyFmt;
         use $crate::CurlyFmtResult;
-        struct CurlyArgumentsInternal {
+        struct CurlyArgumentsInternal<P> {
             $(
                 $argument_name: $argument_type,
             )*
-            delegate_provider: $delegate_type
+            positional: P,
+            delegate_provider: $crate::__curly_provider!(@delegate_type $($delegate_type)?),
         }
-        impl $crate::Provider for CurlyArgumentsInternal {
-            fn provide (&self, context: &$crate::formatting::CurlyContext, key: &str) -> CurlyFmtResult {
+        impl<P: $crate::__private::PositionalArguments> $crate::Provider for CurlyArgumentsInternal<P> {
+            fn provide (&self,
+                        context: &$crate::formatting::CurlyContext,
+                        key: &str) -> CurlyFmtResult {
                 match key {
                     $(
                         stringify!($argument_name) => self.$argument_name.curly_fmt(context),
                     )*
-                    _ => self.delegate_provider.provide(context, key)
+                    _ => match $crate::__private::PositionalArguments::get(&self.positional, key) {
+                        ::std::option::Option::Some(argument) => argument.curly_fmt(context),
+                        ::std::option::Option::None => $crate::Provider::provide(&self.delegate_provider, context, key),
+                    },
                 }
             }
-        }
-        let arguments = CurlyArgumentsInternal {
-            $(
-                $argument_name: $argument_value,
-            )*
-            delegate_provider: $delegate_provider
-        };
-    }};
-    ($format_string:expr, $($argument_name:ident: $argument_type:ty = $argument_value:expr), *,) => {{
-        use $crate::formatting::CurlyFmt;
-        use $crate::CurlyFmtResult;
-        struct CurlyArgumentsInternal {
-            $(
-                $argument_name: $argument_type,
-            )*
-        }
-        impl $crate::Provider for CurlyArgumentsInternal {
-            fn provide (&self,
-                        context: &$crate::formatting::CurlyContext,
-                        key: &str) -> CurlyFmtResult {
+            fn provide_to (&self,
+                           context: &$crate::formatting::CurlyContext,
+                           key: &str,
+                           out: &mut dyn ::std::fmt::Write) -> $crate::CurlyResult<()> {
                 match key {
                     $(
-                        stringify!($argument_name) => self.$argument_name.curly_fmt(context),
+                        stringify!($argument_name) => self.$argument_name.curly_write(context, out),
+                    )*
+                    _ => match $crate::__private::PositionalArguments::get(&self.positional, key) {
+                        ::std::option::Option::Some(argument) => argument.curly_write(context, out),
+                        ::std::option::Option::None => $crate::Provider::provide_to(&self.delegate_provider, context, key, out),
+                    },
+                }
+            }
+            fn is_truthy (&self, key: &str) -> $crate::CurlyResult<bool> {
+                match key {
+                    $(
+                        stringify!($argument_name) => self.$argument_name.curly_is_truthy(),
+                    )*
+                    _ => match $crate::__private::PositionalArguments::get(&self.positional, key) {
+                        ::std::option::Option::Some(argument) => argument.curly_is_truthy(),
+                        ::std::option::Option::None => $crate::Provider::is_truthy(&self.delegate_provider, key),
+                    },
+                }
+            }
+            fn provide_each (&self, key: &str) -> $crate::CurlyResult<$crate::ProviderIter<'_>> {
+                $crate::Provider::provide_each(&self.delegate_provider, key)
+            }
+            fn contains_key (&self, key: &str) -> bool {
+                [$(stringify!($argument_name)),*].contains(&key)
+                    || $crate::__private::PositionalArguments::get(&self.positional, key).is_some()
+                    || $crate::Provider::contains_key(&self.delegate_provider, key)
+            }
+            fn is_safe (&self, key: &str) -> bool {
+                match key {
+                    $(
+                        stringify!($argument_name) => $crate::formatting::CurlyFmt::is_safe(&self.$argument_name),
                     )*
-                    _ => ::std::result::Result::Err($crate::CurlyErrorKind::Generic($crate::CurlyError::from_boxed(format!("Invalid format specifier `{}`.", key))))
+                    _ => match $crate::__private::PositionalArguments::get(&self.positional, key) {
+                        ::std::option::Option::Some(argument) => $crate::formatting::CurlyFmt::is_safe(argument),
+                        ::std::option::Option::None => $crate::Provider::is_safe(&self.delegate_provider, key),
+                    },
                 }
             }
         }
-        let arguments = CurlyArgumentsInternal {
+        CurlyArgumentsInternal {
             $(
                 $argument_name: $argument_value,
             )*
-        };
+            positional: ($($positional_value,)*),
+            delegate_provider: $crate::__curly_provider!(@delegate $($delegate_provider)?),
+        }
     }};
-    ($format_string:expr, $($argument_name:ident: $argument_type:ty = $argument_value:expr), *) => {{
-        curly!($format_string, $($argument_name: $argument_type = $argument_value, )*)
-    }}
+    (@delegate_type $delegate_type:ty) => { $delegate_type };
+    (@delegate_type) => { $crate::__private::NoProvider };
+    (@delegate $delegate_provider:ident) => { $delegate_provider };
+    (@delegate) => { $crate::__private::NoProvider };
+    ($($arguments:tt)*) => {
+        $crate::__curly_provider!(@munch [] [] [] $($arguments)*)
+    };
+}
+
+/// Compile-time validation of literal format strings for `curly!`.
+#[doc(hidden)]
+pub use curly_codegen::__curly_literal;
+
+/// Format `format_string` using the values provided by `provider`.
+///
+/// This parses the format string every time, so use a [`Template`](Template)
+/// to render the same format string many times.
+///
+/// # Errors
+///
+/// [`CurlyErrorKind::Syntax`](errors::CurlyErrorKind::Syntax) if the format string is malformed.
+///
+/// All other errors are from the provider.
+pub fn format(format_string: &str, provider: &dyn Provider) -> CurlyFmtResult {
+    Template::parse(format_string)?.render(provider)
 }
 
 /// A trait that allows implementing structs to provide formatted objects to print.
@@ -98,11 +329,253 @@ pub trait Provider {
     ///
     /// # Errors
     ///
-    /// Type [`CurlyErrorKind::Generic`](errors::CurlyErrorKind::Generic), Message: ``Invalid format specifier: `<KEY>`.``:
+    /// Type [`CurlyErrorKind::Generic`](errors::CurlyErrorKind::Generic), Message: ``Invalid format specifier `<KEY>` ``:
     /// Key `<KEY>` was not found within this struct.
     ///
     /// All other errors are from formatting objects within this struct.
     fn provide(&self, context: &formatting::CurlyContext, key: &str) -> CurlyFmtResult;
+
+    /// Write the formatted result of object `key` in this struct with formatting context `context` to `out`.
+    ///
+    /// By default, this writes the result of [`provide`](Provider::provide).
+    /// `#[derive(Provider)]` implements this with [`CurlyFmt::curly_write`](formatting::CurlyFmt::curly_write),
+    /// so no intermediate `String` is allocated.
+    ///
+    /// # Errors
+    ///
+    /// The same errors as [`provide`](Provider::provide), or [`CurlyErrorKind::Write`](errors::CurlyErrorKind::Write)
+    /// if writing to `out` failed.
+    fn provide_to(
+        &self,
+        context: &formatting::CurlyContext,
+        key: &str,
+        out: &mut dyn std::fmt::Write,
+    ) -> CurlyResult<()> {
+        out.write_str(&self.provide(context, key)?)?;
+        Ok(())
+    }
+
+    /// Whether object `key` in this struct is truthy, for conditional sections (`{#if key}...{/if}`).
+    ///
+    /// By default, the object is truthy if it formats to a non-empty string.
+    /// `#[derive(Provider)]` implements this with [`CurlyFmt::curly_is_truthy`](formatting::CurlyFmt::curly_is_truthy).
+    ///
+    /// # Errors
+    ///
+    /// The same errors as [`provide`](Provider::provide).
+    fn is_truthy(&self, key: &str) -> CurlyResult<bool> {
+        Ok(!self
+            .provide(&formatting::CurlyContext::default(), key)?
+            .is_empty())
+    }
+
+    /// Iterate over the sequence of providers `key` in this struct, for loop sections (`{#each key}...{/each}`).
+    ///
+    /// By default, nothing can be iterated over. `#[derive(Provider)]` implements this for
+    /// fields annotated with `#[curly_each]`.
+    ///
+    /// # Errors
+    ///
+    /// Type [`CurlyErrorKind::Generic`](errors::CurlyErrorKind::Generic), Message: ``Cannot iterate over `<KEY>` ``:
+    /// Key `<KEY>` was not found within this struct, or is not a sequence of providers.
+    fn provide_each(&self, key: &str) -> CurlyResult<ProviderIter<'_>> {
+        Err(CurlyErrorKind::Generic(CurlyError::from_boxed(format!(
+            "Cannot iterate over `{key}`"
+        ))))
+    }
+
+    /// Whether this struct provides object `key`.
+    ///
+    /// Within loop sections, this decides whether a key is provided by the current item
+    /// or by an enclosing scope, and [`or`](Provider::or) falls back for keys that aren't provided.
+    /// `#[derive(Provider)]` implements this with the keys of its [`ProviderSchema`](ProviderSchema).
+    fn contains_key(&self, key: &str) -> bool;
+
+    /// Whether object `key` in this struct is already safe for the output format of a template,
+    /// so it isn't escaped (see [`escaping`](escaping)).
+    ///
+    /// By default, nothing is safe. `#[derive(Provider)]` implements this with
+    /// [`CurlyFmt::is_safe`](formatting::CurlyFmt::is_safe), e.g. for [`SafeString`](escaping::SafeString) fields.
+    fn is_safe(&self, _key: &str) -> bool {
+        false
+    }
+
+    /// Provide keys from `self`, falling back to `fallback` for keys that `self` doesn't
+    /// contain (see [`contains_key`](Provider::contains_key)).
+    fn or<P: Provider>(self, fallback: P) -> combinators::Or<Self, P>
+    where
+        Self: Sized,
+    {
+        combinators::Or {
+            first: self,
+            second: fallback,
+        }
+    }
+
+    /// Provide `value` as `key`, overriding `key` in `self`.
+    ///
+    /// In conditional sections, `value` is truthy according to
+    /// [`CurlyFmt::curly_is_truthy`](formatting::CurlyFmt::curly_is_truthy).
+    fn with<V: formatting::CurlyFmt>(
+        self,
+        key: impl Into<String>,
+        value: V,
+    ) -> combinators::With<Self, V>
+    where
+        Self: Sized,
+    {
+        combinators::With {
+            inner: self,
+            key: key.into(),
+            value,
+        }
+    }
+
+    /// Provide the keys of `self` under `prefix`, e.g. `{user.name}` for `name` with the prefix `user.`.
+    fn prefixed(self, prefix: impl Into<String>) -> combinators::Prefixed<Self>
+    where
+        Self: Sized,
+    {
+        combinators::Prefixed {
+            inner: self,
+            prefix: prefix.into(),
+        }
+    }
+
+    /// Provide only the keys of `self` that are in `keys`.
+    fn only(self, keys: &[&str]) -> combinators::Only<Self>
+    where
+        Self: Sized,
+    {
+        combinators::Only {
+            inner: self,
+            keys: keys.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    /// Transform the errors of `self` with `map`, e.g. to add context.
+    fn map_err<F>(self, map: F) -> combinators::MapErr<Self, F>
+    where
+        Self: Sized,
+        F: Fn(CurlyErrorKind) -> CurlyErrorKind,
+    {
+        combinators::MapErr { inner: self, map }
+    }
+}
+
+/// The keys that a [`Provider`](Provider) can provide, known at compile time.
+///
+/// This is implemented by `#[derive(Provider)]`, and is used by `curly!` to check
+/// literal format strings against a delegate provider.
+pub trait ProviderSchema {
+    /// Every key that [`Provider::provide`](Provider::provide) accepts.
+    const KEYS: &'static [&'static str];
+
+    /// Every key that [`Provider::provide_each`](Provider::provide_each) accepts, which
+    /// can only be used in loop and conditional sections. By default, there are none.
+    const SEQUENCE_KEYS: &'static [&'static str] = &[];
+}
+
+#[doc(hidden)]
+pub mod __private {
+    use crate::formatting::{CurlyContext, CurlyFmt};
+    use crate::{CurlyError, CurlyErrorKind, CurlyFmtResult, Provider, ProviderSchema};
+    use std::marker::PhantomData;
+
+    /// The positional arguments of `curly!`, stored as a tuple.
+    pub trait PositionalArguments {
+        /// Get the positional argument with the index `key`, if `key` is an index.
+        fn get(&self, key: &str) -> Option<&dyn CurlyFmt>;
+    }
+
+    macro_rules! positional_arguments {
+        ($($index:tt: $name:ident),*) => {
+            impl<$($name: CurlyFmt),*> PositionalArguments for ($($name,)*) {
+                #[allow(unused_variables)]
+                fn get(&self, key: &str) -> Option<&dyn CurlyFmt> {
+                    match key.parse::<usize>() {
+                        $(Ok($index) => Some(&self.$index),)*
+                        _ => None,
+                    }
+                }
+            }
+        };
+    }
+
+    positional_arguments!();
+    positional_arguments!(0: A);
+    positional_arguments!(0: A, 1: B);
+    positional_arguments!(0: A, 1: B, 2: C);
+    positional_arguments!(0: A, 1: B, 2: C, 3: D);
+    positional_arguments!(0: A, 1: B, 2: C, 3: D, 4: E);
+    positional_arguments!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F);
+    positional_arguments!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G);
+    positional_arguments!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H);
+    positional_arguments!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H, 8: I);
+    positional_arguments!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H, 8: I, 9: J);
+    positional_arguments!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H, 8: I, 9: J, 10: K);
+    positional_arguments!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H, 8: I, 9: J, 10: K, 11: L);
+
+    /// The delegate provider of `curly!` when there isn't one, which provides nothing.
+    pub struct NoProvider;
+
+    impl Provider for NoProvider {
+        fn provide(&self, _context: &CurlyContext, key: &str) -> CurlyFmtResult {
+            Err(CurlyErrorKind::Generic(CurlyError::from_boxed(format!(
+                "Invalid format specifier `{key}`"
+            ))))
+        }
+
+        fn contains_key(&self, _key: &str) -> bool {
+            false
+        }
+    }
+
+    /// The keys of the [`ProviderSchema`](ProviderSchema) of `T`, if it implements it
+    /// (with inherent impl specialization).
+    ///
+    /// Used as `Schema::<T>::KEYS` with [`NoSchema`](NoSchema) in scope.
+    pub struct Schema<T: ?Sized>(PhantomData<T>);
+
+    impl<T: ProviderSchema + ?Sized> Schema<T> {
+        pub const KEYS: Option<&'static [&'static str]> = Some(T::KEYS);
+        pub const SEQUENCE_KEYS: Option<&'static [&'static str]> = Some(T::SEQUENCE_KEYS);
+    }
+
+    pub trait NoSchema {
+        const KEYS: Option<&'static [&'static str]> = None;
+        const SEQUENCE_KEYS: Option<&'static [&'static str]> = None;
+    }
+
+    impl<T: ?Sized> NoSchema for Schema<T> {}
+
+    /// Whether `keys` contains `key`, or the keys are unknown, usable in constant evaluation.
+    pub const fn schema_contains_key(keys: Option<&[&str]>, key: &str) -> bool {
+        match keys {
+            Some(keys) => contains_key(keys, key),
+            None => true,
+        }
+    }
+
+    /// Whether `keys` contains `key`, usable in constant evaluation.
+    pub const fn contains_key(keys: &[&str], key: &str) -> bool {
+        let key = key.as_bytes();
+        let mut i = 0;
+        while i < keys.len() {
+            let candidate = keys[i].as_bytes();
+            if candidate.len() == key.len() {
+                let mut j = 0;
+                while j < key.len() && candidate[j] == key[j] {
+                    j += 1;
+                }
+                if j == key.len() {
+                    return true;
+                }
+            }
+            i += 1;
+        }
+        false
+    }
 }
 
 /// A [`Result<T, E>`](std::result::Result) with [`E=CurlyErrorKind`](errors::CurlyErrorKind), genericized over `T`
@@ -110,6 +583,8 @@ pub type CurlyResult<T> = Result<T, CurlyErrorKind>;
 /// A [`Result<T, E>`](std::result::Result) with [`E=CurlyErrorKind`](errors::CurlyErrorKind), and [`T=String`](errors::CurlyErrorKind),
 /// intended for use with formatting (hence the name).
 pub type CurlyFmtResult = CurlyResult<String>;
+/// An iterator over a sequence of providers, returned by [`Provider::provide_each`](Provider::provide_each).
+pub type ProviderIter<'a> = Box<dyn Iterator<Item = &'a dyn Provider> + 'a>;
 
 /// Required modules and imports for Curly.
 pub mod prelude {
@@ -0,0 +1,123 @@
+//! Limits on the resources used to render a template, for templates from untrusted sources.
+//!
+//! When a limit is exceeded, rendering stops with
+//! [`CurlyErrorKind::LimitExceeded`](CurlyErrorKind::LimitExceeded).
+
+use crate::prelude::*;
+
+/// Limits on rendering a [`Template`](crate::Template), set with
+/// [`Template::with_limits`](crate::Template::with_limits).
+///
+/// Every limit is unset (unlimited) by default.
+///
+/// # Example
+///
+/// ```rust
+/// use curly::limits::RenderLimits;
+/// use curly::{CurlyErrorKind, Template};
+///
+/// let template = Template::parse("{0:>999999999}")
+///     .unwrap()
+///     .with_limits(RenderLimits::sandboxed());
+/// let values: &[&dyn curly::formatting::CurlyFmt] = &[&1];
+/// assert!(matches!(
+///     template.render(&values),
+///     Err(CurlyErrorKind::LimitExceeded(_))
+/// ));
+/// ```
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash, Default)]
+pub struct RenderLimits {
+    /// The maximum length of the rendered output, in bytes.
+    pub max_output_bytes: Option<usize>,
+    /// The maximum number of placeholders rendered, counting every iteration of a loop section.
+    pub max_placeholders: Option<usize>,
+    /// The maximum depth of nested conditional and loop sections.
+    ///
+    /// Templates are never nested deeper than [`MAX_NESTING`](crate::parsing::MAX_NESTING),
+    /// as deeper templates fail to parse.
+    pub max_nesting: Option<usize>,
+    /// The maximum number of iterations of all loop sections combined.
+    pub max_loop_iterations: Option<usize>,
+    /// The maximum padding width of a placeholder, including width arguments (`name$`).
+    pub max_width: Option<usize>,
+    /// The maximum precision of a placeholder, including precision arguments (`name$` and `.*`).
+    pub max_precision: Option<usize>,
+}
+
+impl RenderLimits {
+    /// No limits, which is the default.
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// Conservative limits for rendering templates written by end users.
+    ///
+    /// These allow 1 MiB of output, 10 000 placeholders, 16 nested sections,
+    /// 10 000 loop iterations, and a width and precision of 1000.
+    pub fn sandboxed() -> Self {
+        Self {
+            max_output_bytes: Some(1024 * 1024),
+            max_placeholders: Some(10_000),
+            max_nesting: Some(16),
+            max_loop_iterations: Some(10_000),
+            max_width: Some(1000),
+            max_precision: Some(1000),
+        }
+    }
+
+    /// Check `value` against the limit `max`, for an error message about `what`.
+    pub(crate) fn check(value: usize, max: Option<usize>, what: &str) -> CurlyResult<()> {
+        match max {
+            Some(max) if value > max => Err(limit_exceeded(format!(
+                "{what} ({value}) exceeds the limit of {max}"
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn limit_exceeded(message: String) -> CurlyErrorKind {
+    CurlyErrorKind::LimitExceeded(CurlyError::from_boxed(message))
+}
+
+/// Limits the number of bytes written to a [`fmt::Write`](std::fmt::Write), keeping whether
+/// the limit was exceeded so that it can be reported instead of [`fmt::Error`](std::fmt::Error).
+pub(crate) struct LimitedWriter<'a> {
+    inner: &'a mut dyn std::fmt::Write,
+    remaining: usize,
+    max: usize,
+    exceeded: bool,
+}
+
+impl<'a> LimitedWriter<'a> {
+    pub(crate) fn new(inner: &'a mut dyn std::fmt::Write, max: usize) -> Self {
+        Self {
+            inner,
+            remaining: max,
+            max,
+            exceeded: false,
+        }
+    }
+
+    /// The error to report instead of `result`, if the limit was exceeded.
+    pub(crate) fn finish(&self, result: CurlyResult<()>) -> CurlyResult<()> {
+        if self.exceeded {
+            return Err(limit_exceeded(format!(
+                "Output exceeds the limit of {} bytes",
+                self.max
+            )));
+        }
+        result
+    }
+}
+
+impl std::fmt::Write for LimitedWriter<'_> {
+    fn write_str(&mut self, text: &str) -> std::fmt::Result {
+        if text.len() > self.remaining {
+            self.exceeded = true;
+            return Err(std::fmt::Error);
+        }
+        self.remaining -= text.len();
+        self.inner.write_str(text)
+    }
+}
@@ -0,0 +1,374 @@
+//! Locale-specific formatting of numbers, such as `1.234.567,89` in German.
+//!
+//! A [`Template`](crate::Template) formats numbers with the decimal separator of its
+//! [`Locale`](Locale), set with [`Template::with_locale`](crate::Template::with_locale), and groups
+//! their digits with its group separator if they have the grouping flag (`{n:,}`). The `_` grouping
+//! flag (`{n:_}`) always groups digits with underscores.
+//!
+//! Integers formatted in another base (`{n:_x}`) are grouped every 4 digits.
+//!
+//! The locale also selects the cases of plural sections (`{n, plural, one {# file} other {# files}}`)
+//! with the CLDR plural rules of its language (see [`Locale::plural_category`](Locale::plural_category)).
+//!
+//! # Example
+//!
+//! ```rust
+//! use curly::locale::Locale;
+//! use curly::Template;
+//!
+//! let template = Template::parse("{0:,.2} {1:,}")
+//!     .unwrap()
+//!     .with_locale(Locale::from_tag("de-DE").unwrap());
+//! let values: &[&dyn curly::formatting::CurlyFmt] = &[&1_234_567.891, &-98_765];
+//! assert_eq!(template.render(&values).unwrap(), "1.234.567,89 -98.765");
+//! ```
+
+/// The number formatting conventions of a language or region.
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash)]
+pub struct Locale {
+    tag: &'static str,
+    decimal_separator: char,
+    group_separator: char,
+    primary_group: usize,
+    secondary_group: usize,
+}
+
+/// The bundled locales, looked up by [`Locale::from_tag`](Locale::from_tag).
+const LOCALES: &[Locale] = &[
+    Locale::new("en", '.', ','),
+    Locale::new("en-IN", '.', ',').with_group_sizes(3, 2),
+    Locale::new("hi", '.', ',').with_group_sizes(3, 2),
+    Locale::new("ja", '.', ','),
+    Locale::new("ko", '.', ','),
+    Locale::new("zh", '.', ','),
+    Locale::new("de", ',', '.'),
+    Locale::new("de-AT", ',', '\u{a0}'),
+    Locale::new("de-CH", '.', '’'),
+    Locale::new("es", ',', '.'),
+    Locale::new("es-MX", '.', ','),
+    Locale::new("it", ',', '.'),
+    Locale::new("it-CH", '.', '’'),
+    Locale::new("nl", ',', '.'),
+    Locale::new("pt", ',', '.'),
+    Locale::new("pt-PT", ',', '\u{a0}'),
+    Locale::new("da", ',', '.'),
+    Locale::new("tr", ',', '.'),
+    Locale::new("id", ',', '.'),
+    Locale::new("fr", ',', '\u{202f}'),
+    Locale::new("fr-CH", ',', '\u{202f}'),
+    Locale::new("sv", ',', '\u{a0}'),
+    Locale::new("nb", ',', '\u{a0}'),
+    Locale::new("fi", ',', '\u{a0}'),
+    Locale::new("pl", ',', '\u{a0}'),
+    Locale::new("cs", ',', '\u{a0}'),
+    Locale::new("sk", ',', '\u{a0}'),
+    Locale::new("hu", ',', '\u{a0}'),
+    Locale::new("ru", ',', '\u{a0}'),
+    Locale::new("uk", ',', '\u{a0}'),
+];
+
+impl Locale {
+    /// A locale with the BCP 47 language tag `tag` and the given separators, which groups digits
+    /// in threes (see [`with_group_sizes`](Locale::with_group_sizes)).
+    pub const fn new(tag: &'static str, decimal_separator: char, group_separator: char) -> Self {
+        Self {
+            tag,
+            decimal_separator,
+            group_separator,
+            primary_group: 3,
+            secondary_group: 3,
+        }
+    }
+
+    /// Group digits by `primary` for the group closest to the decimal separator, and by
+    /// `secondary` for the rest, e.g. `12,34,567` in Indian English is `(3, 2)`.
+    #[must_use]
+    pub const fn with_group_sizes(mut self, primary: usize, secondary: usize) -> Self {
+        self.primary_group = primary;
+        self.secondary_group = secondary;
+        self
+    }
+
+    /// Find the bundled locale for a language tag, such as `de-DE` or `pt_BR.UTF-8`, falling back
+    /// to the locale of its language (`de` for `de-DE`).
+    ///
+    /// Returns `None` if there's no locale for the language.
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let tag = tag.split('.').next().unwrap_or_default().replace('_', "-");
+        let find = |tag: &str| {
+            LOCALES
+                .iter()
+                .find(|locale| locale.tag.eq_ignore_ascii_case(tag))
+                .copied()
+        };
+
+        find(&tag).or_else(|| find(tag.split('-').next().unwrap_or_default()))
+    }
+
+    /// The language tag of this locale, e.g. `en`.
+    pub fn tag(&self) -> &'static str {
+        self.tag
+    }
+
+    /// The separator between the integer and fractional parts of a number, e.g. `.` in `1.5`.
+    pub fn decimal_separator(&self) -> char {
+        self.decimal_separator
+    }
+
+    /// The separator between groups of digits, e.g. `,` in `1,234`.
+    pub fn group_separator(&self) -> char {
+        self.group_separator
+    }
+
+    /// The sizes of the group closest to the decimal separator and of the other groups.
+    pub fn group_sizes(&self) -> (usize, usize) {
+        (self.primary_group, self.secondary_group)
+    }
+}
+
+/// A CLDR plural category, which selects the case of a plural section.
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash)]
+pub enum PluralCategory {
+    Zero,
+    One,
+    Two,
+    Few,
+    Many,
+    Other,
+}
+
+impl PluralCategory {
+    /// The name of this category, as used for the cases of plural sections, e.g. `one`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PluralCategory::Zero => "zero",
+            PluralCategory::One => "one",
+            PluralCategory::Two => "two",
+            PluralCategory::Few => "few",
+            PluralCategory::Many => "many",
+            PluralCategory::Other => "other",
+        }
+    }
+}
+
+/// The operands of a decimal number that CLDR plural rules are based on.
+struct Operands {
+    /// The integer digits (only the last 17 digits, plus 10¹⁷, for larger numbers).
+    i: u64,
+    /// The number of visible fraction digits, with trailing zeros.
+    v: usize,
+    /// The visible fraction digits, with trailing zeros (at most 18).
+    f: u64,
+    /// The visible fraction digits, without trailing zeros (at most 18).
+    t: u64,
+}
+
+impl Operands {
+    fn parse(number: &str) -> Option<Self> {
+        let number = number.trim();
+        let number = number
+            .strip_prefix('-')
+            .or_else(|| number.strip_prefix('+'))
+            .unwrap_or(number);
+        let (integer, fraction) = number.split_once('.').unwrap_or((number, ""));
+        let is_digits = |digits: &str| digits.bytes().all(|byte| byte.is_ascii_digit());
+        if integer.is_empty()
+            || !is_digits(integer)
+            || !is_digits(fraction)
+            || (fraction.is_empty() && number.ends_with('.'))
+        {
+            return None;
+        }
+
+        let parse = |digits: &str| digits.get(..18).unwrap_or(digits).parse().unwrap_or(0);
+        let i = match integer.len() {
+            0..=18 => integer.parse().ok()?,
+            len => integer[len - 17..].parse::<u64>().ok()? + 100_000_000_000_000_000,
+        };
+        Some(Self {
+            i,
+            v: fraction.len(),
+            f: parse(fraction),
+            t: parse(fraction.trim_end_matches('0')),
+        })
+    }
+
+    /// Whether the number is exactly the integer `n` (`n = 1` in CLDR rules).
+    fn is(&self, n: u64) -> bool {
+        self.i == n && self.f == 0
+    }
+}
+
+impl Locale {
+    /// The CLDR plural category of `number` in the language of this locale, for a number
+    /// formatted like `1`, `-2` or `1.50`, or `None` if `number` isn't a decimal number.
+    ///
+    /// Visible fraction digits matter, e.g. `1` is `one` but `1.0` is `other` in English.
+    /// Locales without bundled rules use the English rules.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use curly::locale::{Locale, PluralCategory};
+    ///
+    /// let russian = Locale::from_tag("ru").unwrap();
+    /// assert_eq!(russian.plural_category("21"), Some(PluralCategory::One));
+    /// assert_eq!(russian.plural_category("23"), Some(PluralCategory::Few));
+    /// assert_eq!(russian.plural_category("25"), Some(PluralCategory::Many));
+    /// assert_eq!(russian.plural_category("two"), None);
+    /// ```
+    pub fn plural_category(&self, number: &str) -> Option<PluralCategory> {
+        use PluralCategory::{Few, Many, One, Other};
+
+        let n = Operands::parse(number)?;
+        let (i10, i100) = (n.i % 10, n.i % 100);
+        let millions = n.i != 0 && n.i % 1_000_000 == 0 && n.v == 0;
+        let language = self.tag.split('-').next().unwrap_or_default();
+
+        let category = match language {
+            "ja" | "ko" | "zh" | "id" => Other,
+            "fr" | "pt" | "it" | "es" => {
+                let one = match (self.tag, language) {
+                    ("pt-PT", _) | (_, "it") => n.i == 1 && n.v == 0,
+                    (_, "es") => n.is(1),
+                    _ => n.i <= 1,
+                };
+                if one {
+                    One
+                } else if millions {
+                    Many
+                } else {
+                    Other
+                }
+            }
+            "da" | "hi" | "tr" | "hu" => {
+                let one = match language {
+                    "da" => n.is(1) || (n.t != 0 && n.i <= 1),
+                    "hi" => n.i == 0 || n.is(1),
+                    _ => n.is(1),
+                };
+                if one {
+                    One
+                } else {
+                    Other
+                }
+            }
+            "ru" | "uk" | "pl" => {
+                let one = if language == "pl" {
+                    n.i == 1
+                } else {
+                    i10 == 1 && i100 != 11
+                };
+                if n.v != 0 {
+                    Other
+                } else if one {
+                    One
+                } else if (2..=4).contains(&i10) && !(12..=14).contains(&i100) {
+                    Few
+                } else {
+                    Many
+                }
+            }
+            "cs" | "sk" => match n.i {
+                _ if n.v != 0 => Many,
+                1 => One,
+                2..=4 => Few,
+                _ => Other,
+            },
+            _ if n.i == 1 && n.v == 0 => One,
+            _ => Other,
+        };
+        Some(category)
+    }
+}
+
+/// English (`en`), which formats numbers like `1,234.5`.
+impl Default for Locale {
+    fn default() -> Self {
+        LOCALES[0]
+    }
+}
+
+/// Insert `separator` between groups of the integer `digits`, from the right.
+pub(crate) fn group_digits(
+    digits: &str,
+    separator: char,
+    (primary, secondary): (usize, usize),
+) -> String {
+    let len = digits.chars().count();
+    let mut grouped = String::with_capacity(digits.len() + len / 2);
+    for (idx, ch) in digits.chars().enumerate() {
+        let remaining = len - idx;
+        if idx > 0 && remaining >= primary && (remaining - primary) % secondary == 0 {
+            grouped.push(separator);
+        }
+        grouped.push(ch);
+    }
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup() {
+        assert_eq!(Locale::from_tag("de-DE").unwrap().tag(), "de");
+        assert_eq!(Locale::from_tag("pt_PT.UTF-8").unwrap().tag(), "pt-PT");
+        assert_eq!(
+            Locale::from_tag("FR").unwrap().group_separator(),
+            '\u{202f}'
+        );
+        assert!(Locale::from_tag("xx-YY").is_none());
+        assert_eq!(Locale::default().tag(), "en");
+    }
+
+    #[test]
+    fn plural_rules() {
+        let categories = |tag: &str, numbers: &[&str]| -> Vec<&'static str> {
+            let locale = Locale::from_tag(tag).unwrap();
+            numbers
+                .iter()
+                .map(|number| locale.plural_category(number).unwrap().as_str())
+                .collect()
+        };
+
+        assert_eq!(
+            categories("en", &["0", "1", "-1", "1.0", "2"]),
+            vec!["other", "one", "one", "other", "other"]
+        );
+        assert_eq!(
+            categories("fr", &["0", "1.5", "2", "1000000"]),
+            vec!["one", "one", "other", "many"]
+        );
+        assert_eq!(
+            categories("pl", &["1", "3", "5", "22", "12", "1.5"]),
+            vec!["one", "few", "many", "few", "many", "other"]
+        );
+        assert_eq!(
+            categories("ru", &["1", "11", "21", "2", "14", "0", "0.5"]),
+            vec!["one", "many", "one", "few", "many", "many", "other"]
+        );
+        assert_eq!(
+            categories("cs", &["1", "3", "5", "1.5"]),
+            vec!["one", "few", "other", "many"]
+        );
+        assert_eq!(categories("ja", &["1"]), vec!["other"]);
+        assert_eq!(categories("pt-PT", &["0", "1"]), vec!["other", "one"]);
+        assert_eq!(categories("en", &["123456789012345678901"]), vec!["other"]);
+
+        let english = Locale::default();
+        assert!(english.plural_category("1e3").is_none());
+        assert!(english.plural_category("1.").is_none());
+        assert!(english.plural_category(".5").is_none());
+    }
+
+    #[test]
+    fn grouping() {
+        assert_eq!(group_digits("1234567", ',', (3, 3)), "1,234,567");
+        assert_eq!(group_digits("123", ',', (3, 3)), "123");
+        assert_eq!(group_digits("1234", ',', (3, 3)), "1,234");
+        assert_eq!(group_digits("1234567", ',', (3, 2)), "12,34,567");
+        assert_eq!(group_digits("ff00ff", '_', (4, 4)), "ff_00ff");
+    }
+}
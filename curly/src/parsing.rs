@@ -1,29 +1,17 @@
-/* macro_rules! curly_expect {
-    ($result:ident, $expected:expr, $message:expr, $row:expr, $col:expr) => {
-        if !($expected) {
-            $result = Err($crate::CurlyErrorKind::Syntax($crate::CurlyError::from(
-                format!(
-                    "Expected {} at {}:{}",
-                    $message, $row, $col, $segment, $relcol
-                ),
-            )))
-        }
-    };
-    (explicit $expected:expr, $message:expr, $row:expr, $col:expr) => {
-        if !($expected) {
-            return Err($crate::CurlyErrorKind::Syntax($crate::CurlyError::from(
-                format!("Expected {} at {}:{}", $message, $row, $col),
-            )));
-        }
-    };
-    (implicit $expected:expr, $message:expr, $row:expr, $col:expr) => {
-        if !($expected) {
-            Err($crate::CurlyErrorKind::Syntax($crate::CurlyError::from(
-                format!(
-                    "Expected {} at {}:{} (error is within segment '{}' at {})",
-                    $message, $row, $col
-                ),
-            )))
-        }
-    };
-} */
+//! Format string parsing.
+//!
+//! The parser itself lives in [`curly_parser`](curly_parser) so that it can be shared
+//! with the `curly!` procedural macro, which validates literal format strings at compile time.
+
+use crate::errors::CurlyErrorKind;
+
+pub use curly_parser::{
+    keys, parse, parse_segment, Branch, Case, Conditional, FilterCall, Loop, ParseError,
+    Placeholder, Segment, Select, SelectKind, Span, LOOP_VARIABLES, MAX_NESTING, PLURAL_CATEGORIES,
+};
+
+impl From<ParseError> for CurlyErrorKind {
+    fn from(error: ParseError) -> Self {
+        CurlyErrorKind::Syntax(Box::new(error))
+    }
+}
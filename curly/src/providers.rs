@@ -0,0 +1,431 @@
+use crate::prelude::*;
+use crate::{Provider, ProviderIter};
+use std::borrow::{Borrow, Cow};
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{BuildHasher, Hash};
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// Get the value provided as `key`, or an error if there isn't one.
+fn found<T>(value: Option<T>, key: &str) -> CurlyResult<T> {
+    value.ok_or_else(|| {
+        CurlyErrorKind::Generic(CurlyError::from_boxed(format!(
+            "Invalid format specifier `{key}`"
+        )))
+    })
+}
+
+/// Get the value at index `key`, or an error if `key` isn't a valid index.
+fn positional<'a>(values: &[&'a dyn CurlyFmt], key: &str) -> CurlyResult<&'a dyn CurlyFmt> {
+    found(
+        key.parse::<usize>()
+            .ok()
+            .and_then(|index| values.get(index).copied()),
+        key,
+    )
+}
+
+/// Get the value named `key`, or an error if there isn't one.
+fn named<'a>(values: &[(&str, &'a dyn CurlyFmt)], key: &str) -> CurlyResult<&'a dyn CurlyFmt> {
+    found(
+        values
+            .iter()
+            .find(|(name, _)| *name == key)
+            .map(|(_, value)| *value),
+        key,
+    )
+}
+
+/// Provides positional arguments (`{}` and `{0}`) by index.
+impl Provider for [&dyn CurlyFmt] {
+    fn provide(&self, context: &CurlyContext, key: &str) -> CurlyFmtResult {
+        positional(self, key)?.curly_fmt(context)
+    }
+
+    fn provide_to(
+        &self,
+        context: &CurlyContext,
+        key: &str,
+        out: &mut dyn std::fmt::Write,
+    ) -> CurlyResult<()> {
+        positional(self, key)?.curly_write(context, out)
+    }
+
+    fn is_truthy(&self, key: &str) -> CurlyResult<bool> {
+        positional(self, key)?.curly_is_truthy()
+    }
+
+    fn contains_key(&self, key: &str) -> bool {
+        positional(self, key).is_ok()
+    }
+
+    fn is_safe(&self, key: &str) -> bool {
+        positional(self, key).is_ok_and(CurlyFmt::is_safe)
+    }
+}
+
+/// Provides positional arguments (`{}` and `{0}`) by index.
+impl<const N: usize> Provider for [&dyn CurlyFmt; N] {
+    fn provide(&self, context: &CurlyContext, key: &str) -> CurlyFmtResult {
+        positional(self, key)?.curly_fmt(context)
+    }
+
+    fn provide_to(
+        &self,
+        context: &CurlyContext,
+        key: &str,
+        out: &mut dyn std::fmt::Write,
+    ) -> CurlyResult<()> {
+        positional(self, key)?.curly_write(context, out)
+    }
+
+    fn is_truthy(&self, key: &str) -> CurlyResult<bool> {
+        positional(self, key)?.curly_is_truthy()
+    }
+
+    fn contains_key(&self, key: &str) -> bool {
+        positional(self, key).is_ok()
+    }
+
+    fn is_safe(&self, key: &str) -> bool {
+        positional(self, key).is_ok_and(CurlyFmt::is_safe)
+    }
+}
+
+/// Provides named arguments (`{name}`), assembled at runtime.
+impl Provider for [(&str, &dyn CurlyFmt)] {
+    fn provide(&self, context: &CurlyContext, key: &str) -> CurlyFmtResult {
+        named(self, key)?.curly_fmt(context)
+    }
+
+    fn provide_to(
+        &self,
+        context: &CurlyContext,
+        key: &str,
+        out: &mut dyn std::fmt::Write,
+    ) -> CurlyResult<()> {
+        named(self, key)?.curly_write(context, out)
+    }
+
+    fn is_truthy(&self, key: &str) -> CurlyResult<bool> {
+        named(self, key)?.curly_is_truthy()
+    }
+
+    fn contains_key(&self, key: &str) -> bool {
+        named(self, key).is_ok()
+    }
+
+    fn is_safe(&self, key: &str) -> bool {
+        named(self, key).is_ok_and(CurlyFmt::is_safe)
+    }
+}
+
+/// Provides named arguments (`{name}`), assembled at runtime.
+impl<const N: usize> Provider for [(&str, &dyn CurlyFmt); N] {
+    fn provide(&self, context: &CurlyContext, key: &str) -> CurlyFmtResult {
+        named(self, key)?.curly_fmt(context)
+    }
+
+    fn provide_to(
+        &self,
+        context: &CurlyContext,
+        key: &str,
+        out: &mut dyn std::fmt::Write,
+    ) -> CurlyResult<()> {
+        named(self, key)?.curly_write(context, out)
+    }
+
+    fn is_truthy(&self, key: &str) -> CurlyResult<bool> {
+        named(self, key)?.curly_is_truthy()
+    }
+
+    fn contains_key(&self, key: &str) -> bool {
+        named(self, key).is_ok()
+    }
+
+    fn is_safe(&self, key: &str) -> bool {
+        named(self, key).is_ok_and(CurlyFmt::is_safe)
+    }
+}
+
+/// Provides the value of each entry by its key (`{key}`).
+impl<K, V, S> Provider for HashMap<K, V, S>
+where
+    K: Borrow<str> + Eq + Hash,
+    V: CurlyFmt,
+    S: BuildHasher,
+{
+    fn provide(&self, context: &CurlyContext, key: &str) -> CurlyFmtResult {
+        found(self.get(key), key)?.curly_fmt(context)
+    }
+
+    fn provide_to(
+        &self,
+        context: &CurlyContext,
+        key: &str,
+        out: &mut dyn std::fmt::Write,
+    ) -> CurlyResult<()> {
+        found(self.get(key), key)?.curly_write(context, out)
+    }
+
+    fn is_truthy(&self, key: &str) -> CurlyResult<bool> {
+        found(self.get(key), key)?.curly_is_truthy()
+    }
+
+    fn contains_key(&self, key: &str) -> bool {
+        self.contains_key(key)
+    }
+
+    fn is_safe(&self, key: &str) -> bool {
+        self.get(key).is_some_and(CurlyFmt::is_safe)
+    }
+}
+
+/// Provides the value of each entry by its key (`{key}`).
+impl<K, V> Provider for BTreeMap<K, V>
+where
+    K: Borrow<str> + Ord,
+    V: CurlyFmt,
+{
+    fn provide(&self, context: &CurlyContext, key: &str) -> CurlyFmtResult {
+        found(self.get(key), key)?.curly_fmt(context)
+    }
+
+    fn provide_to(
+        &self,
+        context: &CurlyContext,
+        key: &str,
+        out: &mut dyn std::fmt::Write,
+    ) -> CurlyResult<()> {
+        found(self.get(key), key)?.curly_write(context, out)
+    }
+
+    fn is_truthy(&self, key: &str) -> CurlyResult<bool> {
+        found(self.get(key), key)?.curly_is_truthy()
+    }
+
+    fn contains_key(&self, key: &str) -> bool {
+        self.contains_key(key)
+    }
+
+    fn is_safe(&self, key: &str) -> bool {
+        self.get(key).is_some_and(CurlyFmt::is_safe)
+    }
+}
+
+/// Implements `Provider` for references and smart pointers, forwarding to the pointee.
+macro_rules! forwarding_impls {
+    ($(<$($lifetime:lifetime,)? T $(: $bound:path)?> $ty:ty),*) => {
+        $(
+            impl<$($lifetime,)? T: Provider + ?Sized $(+ $bound)?> Provider for $ty {
+                fn provide(&self, context: &CurlyContext, key: &str) -> CurlyFmtResult {
+                    (**self).provide(context, key)
+                }
+
+                fn provide_to(
+                    &self,
+                    context: &CurlyContext,
+                    key: &str,
+                    out: &mut dyn std::fmt::Write,
+                ) -> CurlyResult<()> {
+                    (**self).provide_to(context, key, out)
+                }
+
+                fn is_truthy(&self, key: &str) -> CurlyResult<bool> {
+                    (**self).is_truthy(key)
+                }
+
+                fn provide_each(&self, key: &str) -> CurlyResult<ProviderIter<'_>> {
+                    (**self).provide_each(key)
+                }
+
+                fn contains_key(&self, key: &str) -> bool {
+                    (**self).contains_key(key)
+                }
+
+                fn is_safe(&self, key: &str) -> bool {
+                    (**self).is_safe(key)
+                }
+            }
+        )*
+    };
+}
+
+forwarding_impls!(
+    <'a, T> &'a T,
+    <'a, T> &'a mut T,
+    <T> Box<T>,
+    <T> Rc<T>,
+    <T> Arc<T>,
+    <'a, T: ToOwned> Cow<'a, T>
+);
+
+/// A provider that calls a closure for every key, created by [`provider_fn`](provider_fn).
+#[derive(Debug, Clone, Copy)]
+pub struct FnProvider<F, K = fn(&str) -> bool> {
+    provide: F,
+    contains_key: K,
+}
+
+/// Create a provider that calls `provide` for every key, which returns `None` for keys
+/// that it doesn't provide.
+///
+/// The closure isn't called to check whether a key is provided, so every key is assumed to be
+/// provided by [`contains_key`](Provider::contains_key), unless the provided keys are
+/// given with [`with_keys`](FnProvider::with_keys), e.g. to layer the provider with [`or`](Provider::or).
+///
+/// # Example
+///
+/// ```rust
+/// use curly::formatting::CurlyFmt;
+/// use curly::providers::provider_fn;
+/// use curly::Template;
+///
+/// let provider = provider_fn(|context, key| Some(key.to_uppercase().curly_fmt(context)));
+/// let template = Template::parse("{hello}, {world:>6}!").unwrap();
+/// assert_eq!(template.render(&provider).unwrap(), "HELLO,  WORLD!");
+/// ```
+pub fn provider_fn<F>(provide: F) -> FnProvider<F>
+where
+    F: Fn(&CurlyContext, &str) -> Option<CurlyFmtResult>,
+{
+    FnProvider {
+        provide,
+        contains_key: |_| true,
+    }
+}
+
+impl<F, K> FnProvider<F, K> {
+    /// Provide only the keys for which `contains_key` returns `true`, which are reported by
+    /// [`Provider::contains_key`](Provider::contains_key).
+    pub fn with_keys<C>(self, contains_key: C) -> FnProvider<F, C>
+    where
+        C: Fn(&str) -> bool,
+    {
+        FnProvider {
+            provide: self.provide,
+            contains_key,
+        }
+    }
+}
+
+impl<F, K> Provider for FnProvider<F, K>
+where
+    F: Fn(&CurlyContext, &str) -> Option<CurlyFmtResult>,
+    K: Fn(&str) -> bool,
+{
+    fn provide(&self, context: &CurlyContext, key: &str) -> CurlyFmtResult {
+        if !(self.contains_key)(key) {
+            return found(None, key);
+        }
+        found((self.provide)(context, key), key)?
+    }
+
+    fn contains_key(&self, key: &str) -> bool {
+        (self.contains_key)(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Template;
+
+    #[test]
+    fn slice_provider() {
+        let template = Template::parse("{} {:>4} {0}").unwrap();
+        let (first, second) = (String::from("a"), String::from("b"));
+        let values: &[&dyn crate::formatting::CurlyFmt] = &[&first, &second];
+        assert_eq!(template.render(&values).unwrap(), "a    b a");
+        assert!(Template::parse("{2}").unwrap().render(&values).is_err());
+    }
+
+    #[test]
+    fn named_provider() {
+        let template = Template::parse("{name:>5} is {age}").unwrap();
+        let (name, age) = (String::from("Ann"), 42u8);
+        let values: &[(&str, &dyn CurlyFmt)] = &[("name", &name), ("age", &age)];
+        assert_eq!(template.render(&values).unwrap(), "  Ann is 42");
+        assert_eq!(
+            template
+                .render(&[("name", &name as &dyn CurlyFmt)])
+                .unwrap_err()
+                .to_string(),
+            "Error: Invalid format specifier `age`"
+        );
+    }
+
+    #[test]
+    fn map_providers() {
+        let template = Template::parse("{a}-{b:>3}").unwrap();
+        let hashed: HashMap<String, u32> = vec![(String::from("a"), 1), (String::from("b"), 2)]
+            .into_iter()
+            .collect();
+        assert_eq!(template.render(&hashed).unwrap(), "1-  2");
+
+        let sorted: BTreeMap<&str, String> = vec![("a", String::from("x"))].into_iter().collect();
+        assert!(template.render(&sorted).is_err());
+        assert!(Provider::contains_key(&sorted, "a"));
+        assert!(!Provider::contains_key(&sorted, "b"));
+    }
+
+    #[test]
+    fn truthiness() {
+        let template = Template::parse("{#if admin}admin{#else}user{/if}").unwrap();
+        let flags: HashMap<&str, bool> = vec![("admin", false)].into_iter().collect();
+        assert_eq!(template.render(&flags).unwrap(), "user");
+        let counts: BTreeMap<&str, u8> = vec![("admin", 0)].into_iter().collect();
+        assert_eq!(template.render(&counts).unwrap(), "user");
+        let counts: HashMap<&str, Option<u8>> = vec![("admin", Some(3))].into_iter().collect();
+        assert_eq!(template.render(&counts).unwrap(), "admin");
+
+        let (yes, zero, empty) = (true, 0.0, String::new());
+        let named: &[(&str, &dyn CurlyFmt)] = &[("admin", &zero)];
+        assert_eq!(template.render(&named).unwrap(), "user");
+        let template = Template::parse("{#if 0}a{/if}{#if 1}b{/if}{#if 2}c{/if}").unwrap();
+        let positional: &[&dyn CurlyFmt] = &[&yes, &false, &empty];
+        assert_eq!(template.render(&positional).unwrap(), "a");
+        let array: [&dyn CurlyFmt; 3] = [&0u8, &1u8, &"x"];
+        assert_eq!(template.render(&array).unwrap(), "bc");
+        assert!(template.render(&[&true as &dyn CurlyFmt]).is_err());
+    }
+
+    #[test]
+    fn forwarding() {
+        let first: BTreeMap<&str, u8> = vec![("a", 1)].into_iter().collect();
+        let second: HashMap<&str, u8> = vec![("b", 2)].into_iter().collect();
+        let providers: Vec<Box<dyn Provider + Send + Sync>> =
+            vec![Box::new(first), Box::new(second)];
+        let shared = Arc::new(providers);
+
+        let template = Template::parse("{a}{b}").unwrap();
+        let layered = (&shared[0]).or(Rc::new(&shared[1]));
+        assert_eq!(template.render(&layered).unwrap(), "12");
+        assert_eq!(template.render(&Cow::Borrowed(&layered)).unwrap(), "12");
+    }
+
+    #[test]
+    fn fn_provider() {
+        let provider = provider_fn(|context, key| match key {
+            "n" => Some(7u8.curly_fmt(context)),
+            _ => None,
+        });
+        let template = Template::parse("{n:03}").unwrap();
+        assert_eq!(template.render(&provider).unwrap(), "007");
+        assert_eq!(
+            Template::parse("{m}")
+                .unwrap()
+                .render(&provider)
+                .unwrap_err()
+                .to_string(),
+            "Error: Invalid format specifier `m`"
+        );
+        assert!(provider.contains_key("n"));
+        assert!(provider.contains_key("m"));
+
+        let provider = provider.with_keys(|key| key == "n");
+        assert_eq!(template.render(&provider).unwrap(), "007");
+        assert!(provider.contains_key("n"));
+        assert!(!provider.contains_key("m"));
+    }
+}
@@ -0,0 +1,707 @@
+//! Rendering templates against any [`Serialize`](serde::Serialize) value, with the `serde` feature.
+
+use crate::prelude::*;
+use crate::{Provider, ProviderIter};
+use serde::ser::{self, Serialize};
+use std::sync::OnceLock;
+
+/// Provides the fields of any [`Serialize`](serde::Serialize) value, without deriving [`Provider`](Provider).
+///
+/// Struct fields and map entries are provided by name, and sequence items by index. Nested values
+/// are provided with dotted paths (`{order.items.0.name}`), and sequences can be iterated over by
+/// loop sections (`{#each order.items}...{/each}`).
+///
+/// Scalar values are formatted like their [`CurlyFmt`](CurlyFmt) implementations, so flags such as
+/// `{count:x}` and `{ratio:.2}` work as expected. Unit values and `None` are formatted as empty strings,
+/// and the value is serialized when it's first rendered.
+///
+/// # Example
+///
+/// ```rust
+/// use curly::serialize::SerdeProvider;
+/// use curly::Template;
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Order {
+///     id: u32,
+///     items: Vec<Item>,
+/// }
+///
+/// #[derive(Serialize)]
+/// struct Item {
+///     name: String,
+///     price: f64,
+/// }
+///
+/// let order = SerdeProvider::new(Order {
+///     id: 255,
+///     items: vec![Item { name: String::from("pen"), price: 1.5 }],
+/// });
+/// let template = Template::parse("#{id:x}: {#each items}{name} ({price:.2}){/each}").unwrap();
+/// assert_eq!(template.render(&order).unwrap(), "#ff: pen (1.50)");
+/// ```
+#[derive(Debug, Clone)]
+pub struct SerdeProvider<T> {
+    value: T,
+    root: OnceLock<Result<Node, String>>,
+}
+
+impl<T: Serialize> SerdeProvider<T> {
+    /// Provide the fields of `value`.
+    pub fn new(value: T) -> Self {
+        Self {
+            value,
+            root: OnceLock::new(),
+        }
+    }
+
+    /// The provided value.
+    pub fn get_ref(&self) -> &T {
+        &self.value
+    }
+
+    /// Unwrap the provided value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    /// The serialized value, serializing it if it hasn't been yet.
+    fn root(&self) -> CurlyResult<&Node> {
+        let root = self.root.get_or_init(|| {
+            self.value
+                .serialize(NodeSerializer)
+                .map_err(|error| error.0)
+        });
+        root.as_ref().map_err(|error| {
+            CurlyErrorKind::Generic(CurlyError::from_boxed(format!(
+                "Cannot serialize value: {error}"
+            )))
+        })
+    }
+}
+
+impl<T: Serialize> Provider for SerdeProvider<T> {
+    fn provide(&self, context: &CurlyContext, key: &str) -> CurlyFmtResult {
+        self.root()?.provide(context, key)
+    }
+
+    fn provide_to(
+        &self,
+        context: &CurlyContext,
+        key: &str,
+        out: &mut dyn std::fmt::Write,
+    ) -> CurlyResult<()> {
+        self.root()?.provide_to(context, key, out)
+    }
+
+    fn is_truthy(&self, key: &str) -> CurlyResult<bool> {
+        self.root()?.is_truthy(key)
+    }
+
+    fn provide_each(&self, key: &str) -> CurlyResult<ProviderIter<'_>> {
+        self.root()?.provide_each(key)
+    }
+
+    fn contains_key(&self, key: &str) -> bool {
+        self.root().is_ok_and(|root| root.contains_key(key))
+    }
+}
+
+/// A serialized value.
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Unit,
+    Bool(bool),
+    Signed(i64),
+    Unsigned(u64),
+    Signed128(i128),
+    Unsigned128(u128),
+    F32(f32),
+    F64(f64),
+    Char(char),
+    String(String),
+    Seq(Vec<Node>),
+    Map(Vec<(String, Node)>),
+}
+
+impl Node {
+    /// Find the value at the dotted path `key`.
+    fn lookup(&self, key: &str) -> Option<&Node> {
+        key.split('.').try_fold(self, |node, part| match node {
+            Node::Map(entries) => entries
+                .iter()
+                .find(|(name, _)| name == part)
+                .map(|(_, value)| value),
+            Node::Seq(items) => items.get(part.parse::<usize>().ok()?),
+            _ => None,
+        })
+    }
+
+    fn get(&self, key: &str) -> CurlyResult<&Node> {
+        self.lookup(key).ok_or_else(|| {
+            CurlyErrorKind::Generic(CurlyError::from_boxed(format!(
+                "Invalid format specifier `{key}`"
+            )))
+        })
+    }
+
+    /// Write this value, which was provided as `key`, to `out`.
+    fn write(
+        &self,
+        context: &CurlyContext,
+        key: &str,
+        out: &mut dyn std::fmt::Write,
+    ) -> CurlyResult<()> {
+        match self {
+            Node::Unit => "".curly_write(context, out),
+            Node::Bool(value) => value.curly_write(context, out),
+            Node::Signed(value) => value.curly_write(context, out),
+            Node::Unsigned(value) => value.curly_write(context, out),
+            Node::Signed128(value) => value.curly_write(context, out),
+            Node::Unsigned128(value) => value.curly_write(context, out),
+            Node::F32(value) => value.curly_write(context, out),
+            Node::F64(value) => value.curly_write(context, out),
+            Node::Char(value) => value.encode_utf8(&mut [0; 4]).curly_write(context, out),
+            Node::String(value) => value.curly_write(context, out),
+            Node::Seq(_) | Node::Map(_) => Err(CurlyErrorKind::Generic(CurlyError::from_boxed(
+                format!("Cannot format `{key}`, which is not a scalar value"),
+            ))),
+        }
+    }
+}
+
+impl Provider for Node {
+    fn provide(&self, context: &CurlyContext, key: &str) -> CurlyFmtResult {
+        let mut provided = String::new();
+        self.provide_to(context, key, &mut provided)?;
+        Ok(provided)
+    }
+
+    fn provide_to(
+        &self,
+        context: &CurlyContext,
+        key: &str,
+        out: &mut dyn std::fmt::Write,
+    ) -> CurlyResult<()> {
+        self.get(key)?.write(context, key, out)
+    }
+
+    fn is_truthy(&self, key: &str) -> CurlyResult<bool> {
+        Ok(match self.get(key)? {
+            Node::Unit => false,
+            Node::Bool(value) => *value,
+            Node::Signed(value) => value.is_truthy(),
+            Node::Unsigned(value) => value.is_truthy(),
+            Node::Signed128(value) => value.is_truthy(),
+            Node::Unsigned128(value) => value.is_truthy(),
+            Node::F32(value) => value.is_truthy(),
+            Node::F64(value) => value.is_truthy(),
+            Node::Char(_) => true,
+            Node::String(value) => value.is_truthy(),
+            Node::Seq(items) => !items.is_empty(),
+            Node::Map(entries) => !entries.is_empty(),
+        })
+    }
+
+    fn provide_each(&self, key: &str) -> CurlyResult<ProviderIter<'_>> {
+        match self.lookup(key) {
+            Some(Node::Seq(items)) => Ok(Box::new(items.iter().map(|item| item as &dyn Provider))),
+            _ => Err(CurlyErrorKind::Generic(CurlyError::from_boxed(format!(
+                "Cannot iterate over `{key}`"
+            )))),
+        }
+    }
+
+    fn contains_key(&self, key: &str) -> bool {
+        self.lookup(key).is_some()
+    }
+}
+
+/// An error while serializing a value.
+#[derive(Debug)]
+struct SerializeError(String);
+
+impl std::fmt::Display for SerializeError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for SerializeError {}
+
+impl ser::Error for SerializeError {
+    fn custom<M: std::fmt::Display>(message: M) -> Self {
+        SerializeError(message.to_string())
+    }
+}
+
+type NodeResult = Result<Node, SerializeError>;
+
+/// Serializes a value into a [`Node`](Node).
+///
+/// Enums are represented like JSON: unit variants as their name, and other variants
+/// as a map from their name to their content.
+struct NodeSerializer;
+
+impl ser::Serializer for NodeSerializer {
+    type Ok = Node;
+    type Error = SerializeError;
+    type SerializeSeq = SeqBuilder;
+    type SerializeTuple = SeqBuilder;
+    type SerializeTupleStruct = SeqBuilder;
+    type SerializeTupleVariant = VariantBuilder<SeqBuilder>;
+    type SerializeMap = MapBuilder;
+    type SerializeStruct = MapBuilder;
+    type SerializeStructVariant = VariantBuilder<MapBuilder>;
+
+    fn serialize_bool(self, value: bool) -> NodeResult {
+        Ok(Node::Bool(value))
+    }
+
+    fn serialize_i8(self, value: i8) -> NodeResult {
+        Ok(Node::Signed(value.into()))
+    }
+
+    fn serialize_i16(self, value: i16) -> NodeResult {
+        Ok(Node::Signed(value.into()))
+    }
+
+    fn serialize_i32(self, value: i32) -> NodeResult {
+        Ok(Node::Signed(value.into()))
+    }
+
+    fn serialize_i64(self, value: i64) -> NodeResult {
+        Ok(Node::Signed(value))
+    }
+
+    fn serialize_i128(self, value: i128) -> NodeResult {
+        Ok(Node::Signed128(value))
+    }
+
+    fn serialize_u8(self, value: u8) -> NodeResult {
+        Ok(Node::Unsigned(value.into()))
+    }
+
+    fn serialize_u16(self, value: u16) -> NodeResult {
+        Ok(Node::Unsigned(value.into()))
+    }
+
+    fn serialize_u32(self, value: u32) -> NodeResult {
+        Ok(Node::Unsigned(value.into()))
+    }
+
+    fn serialize_u64(self, value: u64) -> NodeResult {
+        Ok(Node::Unsigned(value))
+    }
+
+    fn serialize_u128(self, value: u128) -> NodeResult {
+        Ok(Node::Unsigned128(value))
+    }
+
+    fn serialize_f32(self, value: f32) -> NodeResult {
+        Ok(Node::F32(value))
+    }
+
+    fn serialize_f64(self, value: f64) -> NodeResult {
+        Ok(Node::F64(value))
+    }
+
+    fn serialize_char(self, value: char) -> NodeResult {
+        Ok(Node::Char(value))
+    }
+
+    fn serialize_str(self, value: &str) -> NodeResult {
+        Ok(Node::String(value.to_string()))
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> NodeResult {
+        Ok(Node::Seq(
+            value
+                .iter()
+                .map(|&byte| Node::Unsigned(byte.into()))
+                .collect(),
+        ))
+    }
+
+    fn serialize_none(self) -> NodeResult {
+        Ok(Node::Unit)
+    }
+
+    fn serialize_some<V: Serialize + ?Sized>(self, value: &V) -> NodeResult {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> NodeResult {
+        Ok(Node::Unit)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> NodeResult {
+        Ok(Node::Unit)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> NodeResult {
+        Ok(Node::String(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<V: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &V,
+    ) -> NodeResult {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<V: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &V,
+    ) -> NodeResult {
+        Ok(Node::Map(vec![(
+            variant.to_string(),
+            value.serialize(self)?,
+        )]))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqBuilder, SerializeError> {
+        Ok(SeqBuilder(Vec::with_capacity(len.unwrap_or_default())))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqBuilder, SerializeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SeqBuilder, SerializeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<VariantBuilder<SeqBuilder>, SerializeError> {
+        Ok(VariantBuilder {
+            variant,
+            content: self.serialize_seq(Some(len))?,
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<MapBuilder, SerializeError> {
+        Ok(MapBuilder {
+            entries: Vec::with_capacity(len.unwrap_or_default()),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<MapBuilder, SerializeError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<VariantBuilder<MapBuilder>, SerializeError> {
+        Ok(VariantBuilder {
+            variant,
+            content: self.serialize_map(Some(len))?,
+        })
+    }
+}
+
+/// Builds a [`Node::Seq`](Node::Seq) from sequences and tuples.
+struct SeqBuilder(Vec<Node>);
+
+impl SeqBuilder {
+    fn push<V: Serialize + ?Sized>(&mut self, value: &V) -> Result<(), SerializeError> {
+        self.0.push(value.serialize(NodeSerializer)?);
+        Ok(())
+    }
+}
+
+impl ser::SerializeSeq for SeqBuilder {
+    type Ok = Node;
+    type Error = SerializeError;
+
+    fn serialize_element<V: Serialize + ?Sized>(
+        &mut self,
+        value: &V,
+    ) -> Result<(), SerializeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> NodeResult {
+        Ok(Node::Seq(self.0))
+    }
+}
+
+impl ser::SerializeTuple for SeqBuilder {
+    type Ok = Node;
+    type Error = SerializeError;
+
+    fn serialize_element<V: Serialize + ?Sized>(
+        &mut self,
+        value: &V,
+    ) -> Result<(), SerializeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> NodeResult {
+        Ok(Node::Seq(self.0))
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqBuilder {
+    type Ok = Node;
+    type Error = SerializeError;
+
+    fn serialize_field<V: Serialize + ?Sized>(&mut self, value: &V) -> Result<(), SerializeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> NodeResult {
+        Ok(Node::Seq(self.0))
+    }
+}
+
+/// Builds a [`Node::Map`](Node::Map) from maps and structs.
+struct MapBuilder {
+    entries: Vec<(String, Node)>,
+    key: Option<String>,
+}
+
+impl ser::SerializeMap for MapBuilder {
+    type Ok = Node;
+    type Error = SerializeError;
+
+    fn serialize_key<K: Serialize + ?Sized>(&mut self, key: &K) -> Result<(), SerializeError> {
+        let key = match key.serialize(NodeSerializer)? {
+            Node::String(key) => key,
+            Node::Bool(key) => key.to_string(),
+            Node::Signed(key) => key.to_string(),
+            Node::Unsigned(key) => key.to_string(),
+            Node::Signed128(key) => key.to_string(),
+            Node::Unsigned128(key) => key.to_string(),
+            Node::Char(key) => key.to_string(),
+            _ => {
+                return Err(SerializeError(String::from(
+                    "map keys must be strings, characters, integers or booleans",
+                )))
+            }
+        };
+        self.key = Some(key);
+        Ok(())
+    }
+
+    fn serialize_value<V: Serialize + ?Sized>(&mut self, value: &V) -> Result<(), SerializeError> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| SerializeError(String::from("map value without a key")))?;
+        self.entries.push((key, value.serialize(NodeSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> NodeResult {
+        Ok(Node::Map(self.entries))
+    }
+}
+
+impl ser::SerializeStruct for MapBuilder {
+    type Ok = Node;
+    type Error = SerializeError;
+
+    fn serialize_field<V: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &V,
+    ) -> Result<(), SerializeError> {
+        self.entries
+            .push((key.to_string(), value.serialize(NodeSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> NodeResult {
+        Ok(Node::Map(self.entries))
+    }
+}
+
+/// Builds the content of a tuple or struct variant, as a map from its name to its content.
+struct VariantBuilder<B> {
+    variant: &'static str,
+    content: B,
+}
+
+impl ser::SerializeTupleVariant for VariantBuilder<SeqBuilder> {
+    type Ok = Node;
+    type Error = SerializeError;
+
+    fn serialize_field<V: Serialize + ?Sized>(&mut self, value: &V) -> Result<(), SerializeError> {
+        self.content.push(value)
+    }
+
+    fn end(self) -> NodeResult {
+        Ok(Node::Map(vec![(
+            self.variant.to_string(),
+            Node::Seq(self.content.0),
+        )]))
+    }
+}
+
+impl ser::SerializeStructVariant for VariantBuilder<MapBuilder> {
+    type Ok = Node;
+    type Error = SerializeError;
+
+    fn serialize_field<V: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &V,
+    ) -> Result<(), SerializeError> {
+        ser::SerializeStruct::serialize_field(&mut self.content, key, value)
+    }
+
+    fn end(self) -> NodeResult {
+        Ok(Node::Map(vec![(
+            self.variant.to_string(),
+            Node::Map(self.content.entries),
+        )]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Template;
+    use serde::Serialize;
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    struct Config {
+        name: &'static str,
+        port: u16,
+        ratio: f32,
+        offset: i8,
+        debug: bool,
+        initial: char,
+        nickname: Option<String>,
+        tags: Vec<&'static str>,
+        limits: BTreeMap<u8, u32>,
+        mode: Mode,
+        servers: Vec<Server>,
+    }
+
+    #[derive(Serialize)]
+    enum Mode {
+        Fast,
+        #[allow(dead_code)]
+        Custom {
+            level: u8,
+        },
+    }
+
+    #[derive(Serialize)]
+    struct Server {
+        host: &'static str,
+        weight: u8,
+    }
+
+    fn config() -> SerdeProvider<Config> {
+        SerdeProvider::new(Config {
+            name: "web",
+            port: 8080,
+            ratio: 0.25,
+            offset: -1,
+            debug: false,
+            initial: 'w',
+            nickname: None,
+            tags: vec!["a", "b"],
+            limits: vec![(1, 10), (2, 20)].into_iter().collect(),
+            mode: Mode::Fast,
+            servers: vec![
+                Server {
+                    host: "one",
+                    weight: 1,
+                },
+                Server {
+                    host: "two",
+                    weight: 0,
+                },
+            ],
+        })
+    }
+
+    fn render(format_string: &str) -> CurlyFmtResult {
+        Template::parse(format_string)?.render(&config())
+    }
+
+    #[test]
+    fn scalars() {
+        assert_eq!(
+            render(
+                "{name:>5}:{port:x} {ratio:.3} {offset:+} {debug} {initial:^3} [{nickname}] {mode}"
+            )
+            .unwrap(),
+            "  web:1f90 0.250 -1 false  w  [] Fast"
+        );
+        assert_eq!(
+            render("{tags.1} {limits.2:04} {servers.0.host}").unwrap(),
+            "b 0020 one"
+        );
+    }
+
+    #[test]
+    fn sections() {
+        assert_eq!(
+            render("{#if nickname}x{#else if debug}y{#else if tags}{#each servers as s}{s.host}{#if weight}*{/if}{#sep},{/each}{/if}")
+                .unwrap(),
+            "one*,two"
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            render("{tags}").unwrap_err().to_string(),
+            "Error: Cannot format `tags`, which is not a scalar value"
+        );
+        assert_eq!(
+            render("{tags.2}").unwrap_err().to_string(),
+            "Error: Invalid format specifier `tags.2`"
+        );
+        assert!(render("{#each name}{/each}").is_err());
+
+        let custom = SerdeProvider::new(Mode::Custom { level: 3 });
+        assert_eq!(
+            Template::parse("{Custom.level}")
+                .unwrap()
+                .render(&custom)
+                .unwrap(),
+            "3"
+        );
+
+        let invalid = SerdeProvider::new(vec![((1, 2), 3)].into_iter().collect::<BTreeMap<_, _>>());
+        assert_eq!(
+            Template::parse("{a}").unwrap().render(&invalid).unwrap_err().to_string(),
+            "Error: Cannot serialize value: map keys must be strings, characters, integers or booleans"
+        );
+    }
+}
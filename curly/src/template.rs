@@ -0,0 +1,1147 @@
+use crate::escaping::{Escaper, Escaping};
+use crate::filters::FilterRegistry;
+use crate::limits::{LimitedWriter, RenderLimits};
+use crate::locale::Locale;
+use crate::parsing::{self, FilterCall, Segment, SelectKind};
+use crate::prelude::*;
+use crate::{Provider, ProviderIter};
+use std::sync::Arc;
+
+/// A compiled format string, which can be parsed once and rendered many times.
+///
+/// The [`CurlyContext`](CurlyContext) of every format segment is cached, so rendering
+/// only looks up and formats values. Cloning a `Template` is cheap, as the parsed
+/// segments are shared.
+///
+/// The formatted value of a placeholder can be transformed by filters (see [`filters`](crate::filters)),
+/// e.g. `{name | trim | title}`.
+///
+/// Besides format segments, templates can contain conditional sections, which are only
+/// rendered if their key is truthy (see [`Provider::is_truthy`](Provider::is_truthy)):
+/// `{#if key}...{#else if other}...{#else}...{/if}`, and loop sections, which are rendered once for
+/// every item of a sequence of providers (see [`Provider::provide_each`](Provider::provide_each)):
+/// `{#each items as item}...{#sep}...{/each}`.
+///
+/// A template can escape every substituted value for its output format, e.g. HTML, with
+/// [`with_escaping`](Template::with_escaping). Literal text is never escaped (see [`escaping`](crate::escaping)).
+///
+/// Numbers are formatted with the separators of the template's [`Locale`](Locale), set with
+/// [`with_locale`](Template::with_locale) (see [`locale`](crate::locale)).
+///
+/// Templates from untrusted sources should be rendered with limits on their output,
+/// set with [`with_limits`](Template::with_limits) (see [`limits`](crate::limits)).
+///
+/// Select sections render the case matching the value of their key, in the style of ICU
+/// `MessageFormat`. Plural sections select a case by the plural category of the value in the
+/// template's locale, or by its exact value, and `#` within a case is the value:
+/// `{count, plural, =0 {no files} one {# file} other {# files}}`. Other select sections select
+/// a case by value: `{role, select, admin {an admin} other {a user}}`.
+///
+/// Within a loop section, keys are looked up in the current item first (or explicitly with
+/// `{item.key}`), and then in the enclosing scope. The index of the item is `{@index}`, and
+/// `{@first}` and `{@last}` are truthy for the first and last items. The `{#sep}` section
+/// is optional, and is rendered between items.
+///
+/// # Example
+///
+/// ```rust
+/// # #[macro_use] extern crate curly;
+/// use curly::Template;
+///
+/// #[derive(Provider)]
+/// struct Greeting {
+///     name: String,
+/// }
+///
+/// # fn main() {
+/// let template = Template::parse("Hello, {name:>6}!").unwrap();
+/// let greeting = Greeting { name: String::from("world") };
+/// assert_eq!(template.render(&greeting).unwrap(), "Hello,  world!");
+/// # }
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Template {
+    segments: Arc<[TemplateSegment]>,
+    escaping: Escaping,
+    limits: RenderLimits,
+    locale: Locale,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+enum TemplateSegment {
+    Literal(String),
+    Placeholder {
+        context: CurlyContext,
+        filters: Vec<FilterCall>,
+    },
+    Conditional {
+        branches: Vec<(String, Vec<TemplateSegment>)>,
+        otherwise: Option<Vec<TemplateSegment>>,
+    },
+    Loop {
+        key: String,
+        binding: Option<String>,
+        body: Vec<TemplateSegment>,
+        separator: Option<Vec<TemplateSegment>>,
+    },
+    Select {
+        key: String,
+        kind: SelectKind,
+        cases: Vec<(String, Vec<TemplateSegment>)>,
+    },
+}
+
+impl TemplateSegment {
+    fn compile(segments: Vec<Segment>) -> Vec<Self> {
+        segments
+            .into_iter()
+            .map(|segment| match segment {
+                Segment::Literal(text) => TemplateSegment::Literal(text),
+                Segment::Placeholder(mut placeholder) => TemplateSegment::Placeholder {
+                    filters: std::mem::take(&mut placeholder.filters),
+                    context: CurlyContext::from(placeholder),
+                },
+                Segment::Conditional(conditional) => TemplateSegment::Conditional {
+                    branches: conditional
+                        .branches
+                        .into_iter()
+                        .map(|branch| (branch.condition, Self::compile(branch.body)))
+                        .collect(),
+                    otherwise: conditional.otherwise.map(Self::compile),
+                },
+                Segment::Loop(each) => TemplateSegment::Loop {
+                    key: each.key,
+                    binding: each.binding,
+                    body: Self::compile(each.body),
+                    separator: each.separator.map(Self::compile),
+                },
+                Segment::Select(select) => TemplateSegment::Select {
+                    key: select.key,
+                    kind: select.kind,
+                    cases: select
+                        .cases
+                        .into_iter()
+                        .map(|case| (case.selector, Self::compile(case.body)))
+                        .collect(),
+                },
+            })
+            .collect()
+    }
+
+    fn set_locale(segments: &mut [Self], locale: Locale) {
+        for segment in segments {
+            match segment {
+                TemplateSegment::Literal(_) => {}
+                TemplateSegment::Placeholder { context, .. } => context.locale = locale,
+                TemplateSegment::Conditional {
+                    branches,
+                    otherwise,
+                } => {
+                    for (_, body) in branches {
+                        Self::set_locale(body, locale);
+                    }
+                    if let Some(body) = otherwise {
+                        Self::set_locale(body, locale);
+                    }
+                }
+                TemplateSegment::Loop {
+                    body, separator, ..
+                } => {
+                    Self::set_locale(body, locale);
+                    if let Some(separator) = separator {
+                        Self::set_locale(separator, locale);
+                    }
+                }
+                TemplateSegment::Select { cases, .. } => {
+                    for (_, body) in cases {
+                        Self::set_locale(body, locale);
+                    }
+                }
+            }
+        }
+    }
+
+    fn collect_keys<'a>(segments: &'a [Self], keys: &mut Vec<&'a str>) {
+        for segment in segments {
+            match segment {
+                TemplateSegment::Literal(_) => {}
+                TemplateSegment::Placeholder { context, .. } => {
+                    keys.extend(context.flags.width_argument.as_deref());
+                    keys.extend(context.flags.precision_argument.as_deref());
+                    keys.extend(context.specifier.as_deref());
+                }
+                TemplateSegment::Conditional {
+                    branches,
+                    otherwise,
+                } => {
+                    for (condition, body) in branches {
+                        keys.push(condition);
+                        Self::collect_keys(body, keys);
+                    }
+                    if let Some(body) = otherwise {
+                        Self::collect_keys(body, keys);
+                    }
+                }
+                TemplateSegment::Loop { key, separator, .. } => {
+                    keys.push(key);
+                    if let Some(separator) = separator {
+                        Self::collect_keys(separator, keys);
+                    }
+                }
+                TemplateSegment::Select { key, cases, .. } => {
+                    keys.push(key);
+                    for (_, body) in cases {
+                        Self::collect_keys(body, keys);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Template {
+    /// Parse a format string into a `Template`.
+    ///
+    /// # Errors
+    ///
+    /// [`CurlyErrorKind::Syntax`](CurlyErrorKind::Syntax) if the format string is malformed.
+    pub fn parse(format_string: &str) -> CurlyResult<Self> {
+        let segments = TemplateSegment::compile(parsing::parse(format_string)?);
+        Ok(Self {
+            segments: segments.into(),
+            escaping: Escaping::None,
+            limits: RenderLimits::unlimited(),
+            locale: Locale::default(),
+        })
+    }
+
+    /// Escape every value substituted into this template with `escaping`.
+    ///
+    /// Values that are marked as safe, with the `safe` filter or by
+    /// [`Provider::is_safe`](Provider::is_safe), aren't escaped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use curly::escaping::Escaping;
+    /// use curly::Template;
+    ///
+    /// let template = Template::parse("<b>{0}</b> {1|safe}")
+    ///     .unwrap()
+    ///     .with_escaping(Escaping::Html);
+    /// let (name, icon) = (String::from("<script>"), String::from("<i>!</i>"));
+    /// let values: &[&dyn curly::formatting::CurlyFmt] = &[&name, &icon];
+    /// assert_eq!(template.render(&values).unwrap(), "<b>&lt;script&gt;</b> <i>!</i>");
+    /// ```
+    #[must_use]
+    pub fn with_escaping(mut self, escaping: Escaping) -> Self {
+        self.escaping = escaping;
+        self
+    }
+
+    /// The escaping applied to the values substituted into this template.
+    pub fn escaping(&self) -> &Escaping {
+        &self.escaping
+    }
+
+    /// Render this template within `limits`, e.g. [`RenderLimits::sandboxed`](RenderLimits::sandboxed)
+    /// for templates written by end users.
+    #[must_use]
+    pub fn with_limits(mut self, limits: RenderLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// The limits on rendering this template.
+    pub fn limits(&self) -> &RenderLimits {
+        &self.limits
+    }
+
+    /// Format the numbers in this template with the separators of `locale`.
+    ///
+    /// The locale is available to every [`CurlyFmt`](CurlyFmt) implementation as
+    /// [`CurlyContext::locale`](CurlyContext::locale).
+    #[must_use]
+    pub fn with_locale(mut self, locale: Locale) -> Self {
+        let mut segments = self.segments.to_vec();
+        TemplateSegment::set_locale(&mut segments, locale);
+        self.segments = segments.into();
+        self.locale = locale;
+        self
+    }
+
+    /// The locale that numbers in this template are formatted with.
+    pub fn locale(&self) -> &Locale {
+        &self.locale
+    }
+
+    /// Render this template using the values provided by `provider`.
+    ///
+    /// Only the [built-in filters](crate::filters) are available; use
+    /// [`render_with`](Template::render_with) for other filters.
+    ///
+    /// # Errors
+    ///
+    /// Any error returned by the provider while formatting a format segment, or by a filter.
+    pub fn render(&self, provider: &dyn Provider) -> CurlyFmtResult {
+        self.render_with(provider, FilterRegistry::builtin())
+    }
+
+    /// Render this template using the values provided by `provider`, and the filters in `filters`.
+    ///
+    /// # Errors
+    ///
+    /// Any error returned by the provider while formatting a format segment, or by a filter,
+    /// including unknown filters.
+    pub fn render_with(&self, provider: &dyn Provider, filters: &FilterRegistry) -> CurlyFmtResult {
+        let mut rendered = String::new();
+        self.render_fmt_with(provider, filters, &mut rendered)?;
+        Ok(rendered)
+    }
+
+    /// Render this template into a [`fmt::Write`](std::fmt::Write) using the values provided by `provider`.
+    ///
+    /// Values are written with [`Provider::provide_to`](Provider::provide_to), so no
+    /// intermediate `String` is needed for each format segment (unless it has filters).
+    ///
+    /// # Errors
+    ///
+    /// Any error returned by the provider while formatting a format segment, or by a filter, or
+    /// [`CurlyErrorKind::Write`](CurlyErrorKind::Write) if writing to `out` failed.
+    pub fn render_fmt(
+        &self,
+        provider: &dyn Provider,
+        out: &mut dyn std::fmt::Write,
+    ) -> CurlyResult<()> {
+        self.render_fmt_with(provider, FilterRegistry::builtin(), out)
+    }
+
+    /// Render this template into a [`fmt::Write`](std::fmt::Write) using the values provided by `provider`,
+    /// and the filters in `filters`.
+    ///
+    /// # Errors
+    ///
+    /// See [`render_fmt`](Template::render_fmt) and [`render_with`](Template::render_with).
+    pub fn render_fmt_with(
+        &self,
+        provider: &dyn Provider,
+        filters: &FilterRegistry,
+        out: &mut dyn std::fmt::Write,
+    ) -> CurlyResult<()> {
+        let mut renderer = Renderer {
+            filters,
+            escaping: &self.escaping,
+            limits: &self.limits,
+            locale: &self.locale,
+            placeholders: 0,
+            iterations: 0,
+            depth: 0,
+        };
+        match self.limits.max_output_bytes {
+            Some(max) => {
+                let mut limited = LimitedWriter::new(out, max);
+                let result = renderer.render(&self.segments, provider, &mut limited);
+                limited.finish(result)
+            }
+            None => renderer.render(&self.segments, provider, out),
+        }
+    }
+
+    /// Render this template into an [`io::Write`](std::io::Write), such as a file or socket,
+    /// using the values provided by `provider`.
+    ///
+    /// # Errors
+    ///
+    /// Any error returned by the provider while formatting a format segment, or by a filter, or
+    /// [`CurlyErrorKind::Write`](CurlyErrorKind::Write) with the underlying
+    /// [`io::Error`](std::io::Error) if writing to `out` failed.
+    pub fn render_to(&self, provider: &dyn Provider, out: impl std::io::Write) -> CurlyResult<()> {
+        self.render_to_with(provider, FilterRegistry::builtin(), out)
+    }
+
+    /// Render this template into an [`io::Write`](std::io::Write) using the values provided by `provider`,
+    /// and the filters in `filters`.
+    ///
+    /// # Errors
+    ///
+    /// See [`render_to`](Template::render_to) and [`render_with`](Template::render_with).
+    pub fn render_to_with(
+        &self,
+        provider: &dyn Provider,
+        filters: &FilterRegistry,
+        out: impl std::io::Write,
+    ) -> CurlyResult<()> {
+        let mut adapter = IoAdapter {
+            inner: out,
+            error: None,
+        };
+        let result = self.render_fmt_with(provider, filters, &mut adapter);
+        match adapter.error {
+            Some(error) => Err(error.into()),
+            None => result,
+        }
+    }
+
+    /// The keys of every format segment in this template, in order of appearance.
+    ///
+    /// This includes the keys of width and precision arguments (`name$`), which
+    /// come before the key of their format segment, and the conditions of conditional sections.
+    /// Keys within the body of a loop section are resolved against each item first,
+    /// so only the key of the sequence is included.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        let mut keys = Vec::new();
+        TemplateSegment::collect_keys(&self.segments, &mut keys);
+        keys.into_iter()
+    }
+}
+
+/// The state shared by every segment while rendering a template.
+struct Renderer<'a> {
+    filters: &'a FilterRegistry,
+    escaping: &'a Escaping,
+    limits: &'a RenderLimits,
+    locale: &'a Locale,
+    placeholders: usize,
+    iterations: usize,
+    depth: usize,
+}
+
+impl Renderer<'_> {
+    fn render(
+        &mut self,
+        segments: &[TemplateSegment],
+        provider: &dyn Provider,
+        out: &mut dyn std::fmt::Write,
+    ) -> CurlyResult<()> {
+        for segment in segments {
+            match segment {
+                TemplateSegment::Literal(text) => out.write_str(text)?,
+                TemplateSegment::Placeholder { context, filters } => {
+                    self.render_placeholder(context, filters, provider, out)?;
+                }
+                TemplateSegment::Conditional {
+                    branches,
+                    otherwise,
+                } => {
+                    let mut body = otherwise.as_deref();
+                    for (condition, branch) in branches {
+                        if provider.is_truthy(condition)? {
+                            body = Some(branch);
+                            break;
+                        }
+                    }
+                    if let Some(body) = body {
+                        self.render_nested(body, provider, out)?;
+                    }
+                }
+                TemplateSegment::Loop {
+                    key,
+                    binding,
+                    body,
+                    separator,
+                } => {
+                    let mut items = provider.provide_each(key)?.enumerate().peekable();
+                    while let Some((index, item)) = items.next() {
+                        self.iterations += 1;
+                        RenderLimits::check(
+                            self.iterations,
+                            self.limits.max_loop_iterations,
+                            "Number of loop iterations",
+                        )?;
+                        if let Some(separator) = separator.as_deref().filter(|_| index > 0) {
+                            self.render(separator, provider, out)?;
+                        }
+
+                        let scope = LoopScope {
+                            parent: provider,
+                            item,
+                            binding: binding.as_deref(),
+                            index,
+                            last: items.peek().is_none(),
+                        };
+                        self.render_nested(body, &scope, out)?;
+                    }
+                }
+                TemplateSegment::Select { key, kind, cases } => {
+                    let body = self.select_case(key, *kind, cases, provider)?;
+                    self.render_nested(body, provider, out)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Find the body of the case of a select section that matches the value of `key`,
+    /// or of its `other` case.
+    fn select_case<'s>(
+        &self,
+        key: &str,
+        kind: SelectKind,
+        cases: &'s [(String, Vec<TemplateSegment>)],
+        provider: &dyn Provider,
+    ) -> CurlyResult<&'s [TemplateSegment]> {
+        let value = provider.provide(&CurlyContext::default(), key)?;
+        let value = value.trim();
+        let case = match kind {
+            SelectKind::Select => cases.iter().find(|(selector, _)| selector == value),
+            SelectKind::Plural => {
+                let category = self.locale.plural_category(value).ok_or_else(|| {
+                    CurlyErrorKind::Generic(CurlyError::from_boxed(format!(
+                        "Cannot select a plural case for `{key}`, which is `{value}` rather than a number"
+                    )))
+                })?;
+                let number = value.parse::<f64>().ok();
+                #[allow(clippy::float_cmp)]
+                let exact = cases.iter().find(|(selector, _)| {
+                    selector
+                        .strip_prefix('=')
+                        .is_some_and(|exact| exact.parse::<f64>().ok() == number)
+                });
+                exact.or_else(|| {
+                    cases
+                        .iter()
+                        .find(|(selector, _)| selector == category.as_str())
+                })
+            }
+        };
+
+        Ok(case
+            .or_else(|| cases.iter().find(|(selector, _)| selector == "other"))
+            .map_or(&[], |(_, body)| body))
+    }
+
+    /// Render the body of a conditional, loop or select section.
+    fn render_nested(
+        &mut self,
+        segments: &[TemplateSegment],
+        provider: &dyn Provider,
+        out: &mut dyn std::fmt::Write,
+    ) -> CurlyResult<()> {
+        self.depth += 1;
+        RenderLimits::check(self.depth, self.limits.max_nesting, "Nesting depth")?;
+        let result = self.render(segments, provider, out);
+        self.depth -= 1;
+        result
+    }
+
+    fn render_placeholder(
+        &mut self,
+        context: &CurlyContext,
+        filters: &[FilterCall],
+        provider: &dyn Provider,
+        out: &mut dyn std::fmt::Write,
+    ) -> CurlyResult<()> {
+        let key = context.specifier.as_deref().unwrap_or_default();
+        let flags = &context.flags;
+        let resolved;
+        let context = if flags.width_argument.is_none() && flags.precision_argument.is_none() {
+            context
+        } else {
+            resolved = resolve_count_arguments(context, provider)?;
+            &resolved
+        };
+
+        self.placeholders += 1;
+        RenderLimits::check(
+            self.placeholders,
+            self.limits.max_placeholders,
+            "Number of placeholders",
+        )?;
+        if let Some(width) = context.flags.width {
+            RenderLimits::check(width, self.limits.max_width, "Width")?;
+        }
+        if let Some(precision) = context.flags.precision {
+            RenderLimits::check(precision, self.limits.max_precision, "Precision")?;
+        }
+
+        let escape = !self.escaping.is_none()
+            && !filters.iter().any(|filter| filter.name == "safe")
+            && !provider.is_safe(key);
+        if filters.is_empty() && !escape {
+            return provider.provide_to(context, key, out);
+        }
+
+        let mut value = provider.provide(context, key)?;
+        if !filters.is_empty() {
+            value = self
+                .filters
+                .apply(filters, value, self.limits.max_output_bytes)?;
+        }
+        if escape {
+            self.escaping.escape(&value, out)?;
+        } else {
+            out.write_str(&value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Resolve the width and precision arguments (`name$`, `N$` and `.*`) of `context` using `provider`.
+fn resolve_count_arguments(
+    context: &CurlyContext,
+    provider: &dyn Provider,
+) -> CurlyResult<CurlyContext> {
+    let resolve = |key: &str, what: &str| -> CurlyResult<usize> {
+        let value = provider.provide(&CurlyContext::default(), key)?;
+        value.trim().parse().map_err(|_| {
+            CurlyErrorKind::Generic(CurlyError::from_boxed(format!(
+                "Invalid {what} argument `{key}`: expected an unsigned integer, found `{value}`"
+            )))
+        })
+    };
+
+    let mut context = context.clone();
+    if let Some(key) = context.flags.width_argument.take() {
+        context.flags.width = Some(resolve(&key, "width")?);
+    }
+    if let Some(key) = context.flags.precision_argument.take() {
+        context.flags.precision = Some(resolve(&key, "precision")?);
+    }
+    Ok(context)
+}
+
+/// The scope of a single item within a loop section.
+struct LoopScope<'a> {
+    parent: &'a dyn Provider,
+    item: &'a dyn Provider,
+    binding: Option<&'a str>,
+    index: usize,
+    last: bool,
+}
+
+impl<'a> LoopScope<'a> {
+    /// Find the provider of `key`, and the key within that provider.
+    fn resolve<'k>(&self, key: &'k str) -> (&'a dyn Provider, &'k str) {
+        let bound = self
+            .binding
+            .and_then(|binding| key.strip_prefix(binding))
+            .and_then(|rest| rest.strip_prefix('.'));
+
+        match bound {
+            Some(key) => (self.item, key),
+            None if self.item.contains_key(key) => (self.item, key),
+            None => (self.parent, key),
+        }
+    }
+
+    fn variable(&self, key: &str) -> Option<bool> {
+        match key {
+            "@first" => Some(self.index == 0),
+            "@last" => Some(self.last),
+            _ => None,
+        }
+    }
+}
+
+impl Provider for LoopScope<'_> {
+    fn provide(&self, context: &CurlyContext, key: &str) -> CurlyFmtResult {
+        let mut provided = String::new();
+        self.provide_to(context, key, &mut provided)?;
+        Ok(provided)
+    }
+
+    fn provide_to(
+        &self,
+        context: &CurlyContext,
+        key: &str,
+        out: &mut dyn std::fmt::Write,
+    ) -> CurlyResult<()> {
+        if key == "@index" {
+            return self.index.curly_write(context, out);
+        }
+        if let Some(value) = self.variable(key) {
+            return value.curly_write(context, out);
+        }
+
+        let (provider, key) = self.resolve(key);
+        provider.provide_to(context, key, out)
+    }
+
+    fn is_truthy(&self, key: &str) -> CurlyResult<bool> {
+        if key == "@index" {
+            return Ok(self.index.is_truthy());
+        }
+        if let Some(value) = self.variable(key) {
+            return Ok(value);
+        }
+
+        let (provider, key) = self.resolve(key);
+        provider.is_truthy(key)
+    }
+
+    fn provide_each(&self, key: &str) -> CurlyResult<ProviderIter<'_>> {
+        let (provider, key) = self.resolve(key);
+        provider.provide_each(key)
+    }
+
+    fn contains_key(&self, key: &str) -> bool {
+        if parsing::LOOP_VARIABLES.contains(&key) {
+            return true;
+        }
+
+        let (provider, key) = self.resolve(key);
+        provider.contains_key(key)
+    }
+
+    fn is_safe(&self, key: &str) -> bool {
+        if parsing::LOOP_VARIABLES.contains(&key) {
+            return false;
+        }
+
+        let (provider, key) = self.resolve(key);
+        provider.is_safe(key)
+    }
+}
+
+/// Bridges an [`io::Write`](std::io::Write) to a [`fmt::Write`](std::fmt::Write), keeping the
+/// underlying error so that it can be reported instead of [`fmt::Error`](std::fmt::Error).
+struct IoAdapter<W> {
+    inner: W,
+    error: Option<std::io::Error>,
+}
+
+impl<W: std::io::Write> std::fmt::Write for IoAdapter<W> {
+    fn write_str(&mut self, text: &str) -> std::fmt::Result {
+        self.inner.write_all(text.as_bytes()).map_err(|error| {
+            self.error = Some(error);
+            std::fmt::Error
+        })
+    }
+}
+
+impl std::str::FromStr for Template {
+    type Err = CurlyErrorKind;
+
+    fn from_str(format_string: &str) -> CurlyResult<Self> {
+        Self::parse(format_string)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Values;
+
+    impl Provider for Values {
+        fn provide(&self, context: &CurlyContext, key: &str) -> CurlyFmtResult {
+            match key {
+                "a" => "first".curly_fmt(context),
+                "b" => "second".curly_fmt(context),
+                _ => Err(CurlyErrorKind::Generic(CurlyError::from_boxed(format!(
+                    "Invalid format specifier `{key}`"
+                )))),
+            }
+        }
+
+        fn contains_key(&self, key: &str) -> bool {
+            matches!(key, "a" | "b")
+        }
+    }
+
+    #[test]
+    fn render_many() {
+        let template = Template::parse("{a}, {{ {b:-^10} }}").unwrap();
+        let cloned = template.clone();
+        assert_eq!(template.render(&Values).unwrap(), "first, { --second-- }");
+        assert_eq!(cloned.render(&Values).unwrap(), "first, { --second-- }");
+        assert_eq!(template.keys().collect::<Vec<_>>(), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn render_to_writer() {
+        let template = Template::parse("{a} {b}").unwrap();
+        let mut written = Vec::new();
+        template.render_to(&Values, &mut written).unwrap();
+        assert_eq!(written, b"first second");
+    }
+
+    #[test]
+    fn render_to_failing_writer() {
+        struct Full;
+
+        impl std::io::Write for Full {
+            fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("full"))
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let template = Template::parse("{a}").unwrap();
+        let error = template.render_to(&Values, Full).unwrap_err();
+        assert_eq!(error.to_string(), "Write Error: full");
+    }
+
+    #[test]
+    fn count_arguments() {
+        struct WithArguments<'a>(usize, [&'a dyn CurlyFmt; 2]);
+
+        impl Provider for WithArguments<'_> {
+            fn provide(&self, context: &CurlyContext, key: &str) -> CurlyFmtResult {
+                match key {
+                    "width" => self.0.curly_fmt(context),
+                    "0" | "1" => self.1.provide(context, key),
+                    _ => Values.provide(context, key),
+                }
+            }
+
+            fn contains_key(&self, key: &str) -> bool {
+                matches!(key, "width" | "0" | "1") || Values.contains_key(key)
+            }
+        }
+
+        let template = Template::parse("[{a:>width$}] [{b:.*}] [{1:^width$.0$}]").unwrap();
+        let (precision, dynamic) = (3u8, String::from("dynamic"));
+        let arguments = WithArguments(7, [&precision, &dynamic]);
+
+        assert_eq!(
+            template.render(&arguments).unwrap(),
+            "[  first] [sec] [  dyn  ]"
+        );
+        assert_eq!(
+            template.keys().collect::<Vec<_>>(),
+            vec!["width", "a", "0", "b", "width", "0", "1"]
+        );
+    }
+
+    #[test]
+    fn invalid_count_argument() {
+        let template = Template::parse("{a:b$}").unwrap();
+        let error = template.render(&Values).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Error: Invalid width argument `b`: expected an unsigned integer, found `second`"
+        );
+    }
+
+    #[test]
+    fn conditionals() {
+        struct Flags;
+
+        impl Provider for Flags {
+            fn provide(&self, context: &CurlyContext, key: &str) -> CurlyFmtResult {
+                match key {
+                    "empty" => "".curly_fmt(context),
+                    _ => Values.provide(context, key),
+                }
+            }
+
+            fn is_truthy(&self, key: &str) -> CurlyResult<bool> {
+                match key {
+                    "yes" => Ok(true),
+                    "no" => Ok(false),
+                    _ => Ok(!self.provide(&CurlyContext::default(), key)?.is_empty()),
+                }
+            }
+
+            fn contains_key(&self, key: &str) -> bool {
+                matches!(key, "empty" | "yes" | "no") || Values.contains_key(key)
+            }
+        }
+
+        let template =
+            Template::parse("{#if no}x{#else if empty}y{#else if a}[{a}{#if yes}!{/if}]{/if}")
+                .unwrap();
+        assert_eq!(template.render(&Flags).unwrap(), "[first!]");
+        assert_eq!(
+            template.keys().collect::<Vec<_>>(),
+            vec!["no", "empty", "a", "a", "yes"]
+        );
+
+        let template = Template::parse("{#if no}x{#else}{b}{/if}").unwrap();
+        assert_eq!(template.render(&Flags).unwrap(), "second");
+        assert!(Template::parse("{#if c}{/if}")
+            .unwrap()
+            .render(&Flags)
+            .is_err());
+    }
+
+    #[test]
+    fn loops() {
+        struct Item(String, u32);
+
+        impl Provider for Item {
+            fn provide(&self, context: &CurlyContext, key: &str) -> CurlyFmtResult {
+                match key {
+                    "name" => self.0.curly_fmt(context),
+                    "price" => self.1.curly_fmt(context),
+                    _ => Values.provide(context, key),
+                }
+            }
+
+            fn contains_key(&self, key: &str) -> bool {
+                matches!(key, "name" | "price")
+            }
+        }
+
+        struct Order(Vec<Item>);
+
+        impl Provider for Order {
+            fn provide(&self, context: &CurlyContext, key: &str) -> CurlyFmtResult {
+                match key {
+                    "name" => "order".curly_fmt(context),
+                    _ => Values.provide(context, key),
+                }
+            }
+
+            fn contains_key(&self, key: &str) -> bool {
+                key == "name" || Values.contains_key(key)
+            }
+
+            fn provide_each(&self, key: &str) -> CurlyResult<ProviderIter<'_>> {
+                match key {
+                    "items" => Ok(Box::new(self.0.iter().map(|item| item as &dyn Provider))),
+                    _ => Err(CurlyErrorKind::Generic(CurlyError::from_boxed(format!(
+                        "Cannot iterate over `{key}`"
+                    )))),
+                }
+            }
+        }
+
+        let order = Order(vec![
+            Item(String::from("pen"), 3),
+            Item(String::from("ink"), 12),
+        ]);
+
+        let template =
+            Template::parse("{#each items as item}{@index}.{item.name:>4}={price}{a}{#if @last}.{/if}{#sep}, {/each}")
+                .unwrap();
+        assert_eq!(
+            template.render(&order).unwrap(),
+            "0. pen=3first, 1. ink=12first."
+        );
+        assert_eq!(template.keys().collect::<Vec<_>>(), vec!["items"]);
+
+        let template =
+            Template::parse("{#each items}{#if @first}{name}:{/if} {name}{/each}").unwrap();
+        assert_eq!(template.render(&order).unwrap(), "pen: pen ink");
+
+        let template = Template::parse("{#each items}{name}{@last:!/,} {/each}").unwrap();
+        assert_eq!(template.render(&order).unwrap(), "pen, ink! ");
+
+        let order = Order(Vec::new());
+        assert_eq!(template.render(&order).unwrap(), "");
+        assert!(Template::parse("{#each a}{/each}")
+            .unwrap()
+            .render(&order)
+            .is_err());
+    }
+
+    #[test]
+    fn filters() {
+        let template =
+            Template::parse(r#"{a | upper}, {b:>8 | replace:" ",_ | truncate:7}"#).unwrap();
+        assert_eq!(template.render(&Values).unwrap(), "FIRST, __secon");
+
+        let mut filters = FilterRegistry::empty();
+        filters.register("shout", |value: String, _arguments: &[String]| {
+            Ok(format!("{value}!"))
+        });
+        let template = Template::parse("{a|shout}").unwrap();
+        assert!(template.render(&Values).is_err());
+        assert_eq!(template.render_with(&Values, &filters).unwrap(), "first!");
+    }
+
+    #[test]
+    fn escaping() {
+        struct Markup;
+
+        impl Provider for Markup {
+            fn provide(&self, context: &CurlyContext, key: &str) -> CurlyFmtResult {
+                match key {
+                    "safe" => crate::escaping::SafeString::from("<br>").curly_fmt(context),
+                    _ => "<a & 'b'>".curly_fmt(context),
+                }
+            }
+
+            fn contains_key(&self, _key: &str) -> bool {
+                true
+            }
+
+            fn is_safe(&self, key: &str) -> bool {
+                key == "safe"
+            }
+        }
+
+        let template = Template::parse("<p title=\"{a}\">{a|upper}{a|safe}{safe}</p>")
+            .unwrap()
+            .with_escaping(Escaping::Html);
+        assert_eq!(
+            template.render(&Markup).unwrap(),
+            "<p title=\"&lt;a &amp; &#x27;b&#x27;&gt;\">&lt;A &amp; &#x27;B&#x27;&gt;<a & 'b'><br></p>"
+        );
+        assert_eq!(template.escaping(), &Escaping::Html);
+        assert_ne!(template, template.clone().with_escaping(Escaping::None));
+
+        let template = Template::parse("echo {a}; {a:>10}")
+            .unwrap()
+            .with_escaping(Escaping::Shell);
+        assert_eq!(
+            template.render(&Markup).unwrap(),
+            r"echo '<a & '\''b'\''>'; ' <a & '\''b'\''>'"
+        );
+    }
+
+    #[test]
+    fn limits() {
+        let limits = RenderLimits {
+            max_output_bytes: Some(12),
+            max_placeholders: Some(2),
+            max_width: Some(8),
+            max_precision: Some(2),
+            ..RenderLimits::default()
+        };
+        let render = |format_string: &str| {
+            Template::parse(format_string)
+                .unwrap()
+                .with_limits(limits)
+                .render(&Values)
+                .map_err(|error| error.to_string())
+        };
+
+        assert_eq!(render("{a:>8}{b:.2}").unwrap(), "   firstse");
+        assert_eq!(
+            render("{a:>9}").unwrap_err(),
+            "Limit Exceeded: Width (9) exceeds the limit of 8"
+        );
+        assert_eq!(
+            render("{b:.3}").unwrap_err(),
+            "Limit Exceeded: Precision (3) exceeds the limit of 2"
+        );
+        assert_eq!(
+            render("{a}{a}{a}").unwrap_err(),
+            "Limit Exceeded: Number of placeholders (3) exceeds the limit of 2"
+        );
+        assert_eq!(
+            render("{b} and {a}").unwrap_err(),
+            "Limit Exceeded: Output exceeds the limit of 12 bytes"
+        );
+        assert_eq!(
+            render("{a | replace:\"\",xx | upper}").unwrap_err(),
+            "Limit Exceeded: Output of filter `replace` (17) exceeds the limit of 12"
+        );
+        let amplified = format!("{{0{}}}", r#"|replace:"",aaaaaaaaaaaaaaaa"#.repeat(8));
+        assert!(matches!(
+            Template::parse(&amplified)
+                .unwrap()
+                .with_limits(RenderLimits::sandboxed())
+                .render(&[&"abc" as &dyn CurlyFmt]),
+            Err(CurlyErrorKind::LimitExceeded(_))
+        ));
+        assert!(matches!(
+            Template::parse("{a:>999999999}")
+                .unwrap()
+                .with_limits(RenderLimits::sandboxed())
+                .render(&Values),
+            Err(CurlyErrorKind::LimitExceeded(_))
+        ));
+    }
+
+    #[test]
+    fn section_limits() {
+        struct Many(Vec<Values>);
+
+        impl Provider for Many {
+            fn provide(&self, context: &CurlyContext, key: &str) -> CurlyFmtResult {
+                Values.provide(context, key)
+            }
+
+            fn contains_key(&self, key: &str) -> bool {
+                Values.contains_key(key)
+            }
+
+            fn provide_each(&self, _key: &str) -> CurlyResult<ProviderIter<'_>> {
+                Ok(Box::new(self.0.iter().map(|item| item as &dyn Provider)))
+            }
+        }
+
+        let limits = RenderLimits {
+            max_nesting: Some(2),
+            max_loop_iterations: Some(4),
+            ..RenderLimits::default()
+        };
+        let render = |format_string: &str, items: usize| {
+            let many = Many((0..items).map(|_| Values).collect());
+            Template::parse(format_string)
+                .unwrap()
+                .with_limits(limits)
+                .render(&many)
+                .map_err(|error| error.to_string())
+        };
+
+        assert_eq!(render("{#each x}{#if a}.{/if}{/each}", 2).unwrap(), "..");
+        assert_eq!(
+            render("{#each x}{#if a}{#if b}.{/if}{/if}{/each}", 1).unwrap_err(),
+            "Limit Exceeded: Nesting depth (3) exceeds the limit of 2"
+        );
+        assert_eq!(
+            render("{#each x}.{/each}", 5).unwrap_err(),
+            "Limit Exceeded: Number of loop iterations (5) exceeds the limit of 4"
+        );
+    }
+
+    #[test]
+    fn locale() {
+        let values: &[&dyn CurlyFmt] = &[&"yes", &12_345.5];
+        let template = Template::parse("{#if 0}{1:,.1}{/if} {1:_}").unwrap();
+        assert_eq!(template.render(&values).unwrap(), "12,345.5 12_345.5");
+
+        let template = template.with_locale(Locale::from_tag("de").unwrap());
+        assert_eq!(template.render(&values).unwrap(), "12.345,5 12_345,5");
+        assert_eq!(template.locale().tag(), "de");
+    }
+
+    #[test]
+    fn selects() {
+        let files = "{0, plural, =0 {no files} one {# file} few {# files (few)} other {# files}}";
+        let template = Template::parse(files).unwrap();
+        let render = |template: &Template, count: &dyn CurlyFmt| template.render(&[count]).unwrap();
+        assert_eq!(render(&template, &0), "no files");
+        assert_eq!(render(&template, &1), "1 file");
+        assert_eq!(render(&template, &22), "22 files");
+        assert_eq!(render(&template, &1.5), "1.5 files");
+
+        let template = template.with_locale(Locale::from_tag("ru").unwrap());
+        assert_eq!(render(&template, &0), "no files");
+        assert_eq!(render(&template, &21), "21 file");
+        assert_eq!(render(&template, &22), "22 files (few)");
+        assert_eq!(render(&template, &1.5), "1,5 files");
+
+        let template = Template::parse(
+            "{0, select, admin {an admin} other {a {0}}} {1, plural, one {#} other {# times}}",
+        )
+        .unwrap();
+        let values: &[&dyn CurlyFmt] = &[&"admin", &1];
+        assert_eq!(template.render(&values).unwrap(), "an admin 1");
+        let values: &[&dyn CurlyFmt] = &[&"guest", &2];
+        assert_eq!(template.render(&values).unwrap(), "a guest 2 times");
+        let values: &[&dyn CurlyFmt] = &[&"guest", &"two"];
+        assert_eq!(
+            template.render(&values).unwrap_err().to_string(),
+            "Error: Cannot select a plural case for `1`, which is `two` rather than a number"
+        );
+    }
+
+    #[test]
+    fn render_error() {
+        let template = Template::parse("{c}").unwrap();
+        assert!(template.render(&Values).is_err());
+    }
+
+    #[test]
+    fn parse_error() {
+        assert!(matches!(
+            Template::parse("{a"),
+            Err(CurlyErrorKind::Syntax(_))
+        ));
+    }
+
+    #[test]
+    fn send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Template>();
+    }
+}
@@ -0,0 +1,293 @@
+//! Human-readable formatting of quantities, such as durations and byte sizes.
+//!
+//! [`Duration`](std::time::Duration)s are formatted according to the custom flags:
+//!
+//! | Pattern | Example | Description |
+//! |---------|---------|-------------|
+//! | `human` | `2m 5s` | The days, hours, minutes and seconds, or the milliseconds, microseconds or nanoseconds of durations under a second (the default) |
+//! | `secs` | `125.25` | The seconds |
+//! | `secs.N` | `125.250` | The seconds with `N` decimal places, e.g. `{elapsed:secs.3}` |
+//! | `millis` | `125250` | The whole milliseconds |
+//! | `iso8601` | `PT2M5.25S` | An ISO 8601 duration |
+//!
+//! [`ByteSize`](ByteSize)s are formatted with binary units by default:
+//!
+//! | Pattern | Example | Description |
+//! |---------|---------|-------------|
+//! | `iec` | `1.5 MiB` | Binary (IEC) units, which are powers of 1024 (the default) |
+//! | `si` | `1.6 MB` | Decimal (SI) units, which are powers of 1000 |
+//! | `iec.N`, `si.N` | `1.50 MiB` | With `N` decimal places instead of 1 |
+//! | `bytes` | `1572864` | The number of bytes |
+//!
+//! The number of decimal places `N` is at most 1000.
+//!
+//! # Example
+//!
+//! ```rust
+//! use curly::units::ByteSize;
+//! use curly::Template;
+//! use std::time::Duration;
+//!
+//! let elapsed = Duration::from_millis(125_250);
+//! let size = ByteSize(1536 * 1024);
+//! let values: &[&dyn curly::formatting::CurlyFmt] = &[&elapsed, &size];
+//! let template = Template::parse("{0} ({0:secs.3}s), {1:iec}").unwrap();
+//! assert_eq!(template.render(&values).unwrap(), "2m 5s (125.250s), 1.5 MiB");
+//! ```
+
+use crate::prelude::*;
+use std::fmt::Write;
+use std::time::Duration;
+
+/// The maximum number of decimal places of a pattern such as `secs.3`, which is the
+/// precision limit of [`RenderLimits::sandboxed`](crate::limits::RenderLimits::sandboxed).
+const MAX_DECIMALS: usize = 1000;
+
+/// Split a pattern such as `secs.3` into its name and number of decimal places.
+fn split_decimals<'p>(
+    pattern: &'p str,
+    type_name: &str,
+    expected: &str,
+) -> CurlyResult<(&'p str, Option<usize>)> {
+    match pattern.split_once('.') {
+        None => Ok((pattern, None)),
+        Some((name, decimals)) => match decimals.parse() {
+            Ok(decimals) if decimals > MAX_DECIMALS => {
+                Err(CurlyErrorKind::Generic(CurlyError::from_boxed(format!(
+                    "Invalid pattern `{pattern}` for `{type_name}`, \
+                     the number of decimal places exceeds the limit of {MAX_DECIMALS}"
+                ))))
+            }
+            Ok(decimals) => Ok((name, Some(decimals))),
+            Err(_) => Err(invalid_pattern(pattern, type_name, expected)),
+        },
+    }
+}
+
+fn invalid_pattern(pattern: &str, type_name: &str, expected: &str) -> CurlyErrorKind {
+    CurlyErrorKind::Generic(CurlyError::from_boxed(format!(
+        "Invalid pattern `{pattern}` for `{type_name}`, expected {expected}"
+    )))
+}
+
+/// Formats the duration according to the custom flags, as `human` by default.
+impl CurlyFmt for Duration {
+    fn curly_fmt(&self, context: &CurlyContext) -> CurlyFmtResult {
+        let mut formatted = String::new();
+        self.curly_write(context, &mut formatted)?;
+        Ok(formatted)
+    }
+
+    fn curly_write(
+        &self,
+        context: &CurlyContext,
+        out: &mut dyn std::fmt::Write,
+    ) -> CurlyResult<()> {
+        const EXPECTED: &str = "`human`, `secs`, `secs.N`, `millis` or `iso8601`";
+
+        let pattern = context.custom_pattern().unwrap_or("human");
+        let mut formatted = String::new();
+        match split_decimals(pattern, "Duration", EXPECTED)? {
+            ("human", None) => write_human(*self, &mut formatted)?,
+            ("secs", None) => write!(formatted, "{}", self.as_secs_f64())?,
+            ("secs", Some(decimals)) => write!(formatted, "{:.*}", decimals, self.as_secs_f64())?,
+            ("millis", None) => write!(formatted, "{}", self.as_millis())?,
+            ("iso8601", None) => write_iso8601(*self, &mut formatted)?,
+            _ => return Err(invalid_pattern(pattern, "Duration", EXPECTED)),
+        }
+        context.pad_to(&formatted, Alignment::Left, out)?;
+        Ok(())
+    }
+
+    fn curly_is_truthy(&self) -> CurlyResult<bool> {
+        Ok(!self.is_zero())
+    }
+}
+
+/// Write `duration` as days, hours, minutes and seconds (`1d 2h 5s`), or as the largest
+/// sub-second unit for durations under a second (`250ms`).
+fn write_human(duration: Duration, out: &mut dyn std::fmt::Write) -> std::fmt::Result {
+    let secs = duration.as_secs();
+    if secs == 0 {
+        return match duration.subsec_nanos() {
+            0 => out.write_str("0s"),
+            nanos if nanos >= 1_000_000 => write!(out, "{}ms", nanos / 1_000_000),
+            nanos if nanos >= 1_000 => write!(out, "{}µs", nanos / 1_000),
+            nanos => write!(out, "{nanos}ns"),
+        };
+    }
+
+    let units = [
+        (secs / 86_400, 'd'),
+        (secs / 3600 % 24, 'h'),
+        (secs / 60 % 60, 'm'),
+        (secs % 60, 's'),
+    ];
+    let mut separator = "";
+    for &(count, unit) in units.iter().filter(|&&(count, _)| count > 0) {
+        write!(out, "{separator}{count}{unit}")?;
+        separator = " ";
+    }
+    Ok(())
+}
+
+/// Write `duration` as an ISO 8601 duration, such as `P1DT2H5.25S`.
+fn write_iso8601(duration: Duration, out: &mut dyn std::fmt::Write) -> std::fmt::Result {
+    let secs = duration.as_secs();
+    let nanos = duration.subsec_nanos();
+    out.write_char('P')?;
+    if secs >= 86_400 {
+        write!(out, "{}D", secs / 86_400)?;
+    }
+    if secs % 86_400 == 0 && nanos == 0 {
+        return if secs == 0 {
+            out.write_str("T0S")
+        } else {
+            Ok(())
+        };
+    }
+
+    out.write_char('T')?;
+    for &(count, unit) in &[(secs / 3600 % 24, 'H'), (secs / 60 % 60, 'M')] {
+        if count > 0 {
+            write!(out, "{count}{unit}")?;
+        }
+    }
+    if secs % 60 != 0 || nanos > 0 {
+        write!(out, "{}", secs % 60)?;
+        if nanos > 0 {
+            write!(out, ".{}", format!("{nanos:09}").trim_end_matches('0'))?;
+        }
+        out.write_char('S')?;
+    }
+    Ok(())
+}
+
+/// A number of bytes, formatted with binary (IEC) or decimal (SI) units, such as `1.5 MiB`.
+///
+/// See the [module documentation](self) for the supported patterns.
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Hash, Default)]
+pub struct ByteSize(pub u64);
+
+impl From<u64> for ByteSize {
+    fn from(bytes: u64) -> Self {
+        ByteSize(bytes)
+    }
+}
+
+impl CurlyFmt for ByteSize {
+    fn curly_fmt(&self, context: &CurlyContext) -> CurlyFmtResult {
+        let mut formatted = String::new();
+        self.curly_write(context, &mut formatted)?;
+        Ok(formatted)
+    }
+
+    fn curly_write(
+        &self,
+        context: &CurlyContext,
+        out: &mut dyn std::fmt::Write,
+    ) -> CurlyResult<()> {
+        const EXPECTED: &str = "`iec`, `si`, `iec.N`, `si.N` or `bytes`";
+        const IEC: [&str; 7] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+        const SI: [&str; 7] = ["B", "kB", "MB", "GB", "TB", "PB", "EB"];
+
+        let pattern = context.custom_pattern().unwrap_or("iec");
+        let (base, units, decimals) = match split_decimals(pattern, "ByteSize", EXPECTED)? {
+            ("iec", decimals) => (1024, IEC, decimals),
+            ("si", decimals) => (1000, SI, decimals),
+            ("bytes", None) => return self.0.curly_write(context, out),
+            _ => return Err(invalid_pattern(pattern, "ByteSize", EXPECTED)),
+        };
+
+        let mut formatted = String::new();
+        let mut scale = 1;
+        let mut unit = 0;
+        while unit + 1 < units.len() && self.0 / scale >= base {
+            scale *= base;
+            unit += 1;
+        }
+        if unit == 0 {
+            write!(formatted, "{} B", self.0)?;
+        } else {
+            #[allow(clippy::cast_precision_loss)]
+            let value = self.0 as f64 / scale as f64;
+            write!(
+                formatted,
+                "{:.*} {}",
+                decimals.unwrap_or(1),
+                value,
+                units[unit]
+            )?;
+        }
+        context.pad_to(&formatted, Alignment::Left, out)?;
+        Ok(())
+    }
+
+    fn curly_is_truthy(&self) -> CurlyResult<bool> {
+        Ok(self.0 != 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(value: &dyn CurlyFmt, segment: &str) -> CurlyFmtResult {
+        value.curly_fmt(&CurlyContext::from_segment(segment, 1, 1).unwrap())
+    }
+
+    #[test]
+    fn durations() {
+        let elapsed = Duration::from_millis(125_250);
+        assert_eq!(format(&elapsed, "x:human").unwrap(), "2m 5s");
+        assert_eq!(format(&elapsed, "x:>8").unwrap(), "   2m 5s");
+        assert_eq!(format(&elapsed, "x:secs").unwrap(), "125.25");
+        assert_eq!(format(&elapsed, "x:secs.3").unwrap(), "125.250");
+        assert_eq!(format(&elapsed, "x:millis").unwrap(), "125250");
+        assert_eq!(format(&elapsed, "x:iso8601").unwrap(), "PT2M5.25S");
+
+        let long = Duration::from_secs(86_400 + 7200 + 5);
+        assert_eq!(format(&long, "x").unwrap(), "1d 2h 5s");
+        assert_eq!(format(&long, "x:iso8601").unwrap(), "P1DT2H5S");
+        assert_eq!(
+            format(&Duration::from_secs(48 * 3600), "x:iso8601").unwrap(),
+            "P2D"
+        );
+
+        assert_eq!(format(&Duration::ZERO, "x").unwrap(), "0s");
+        assert_eq!(format(&Duration::ZERO, "x:iso8601").unwrap(), "PT0S");
+        assert_eq!(format(&Duration::from_micros(1500), "x").unwrap(), "1ms");
+        assert_eq!(format(&Duration::from_nanos(1500), "x").unwrap(), "1µs");
+        assert_eq!(
+            format(&elapsed, "x:secs.x").unwrap_err().to_string(),
+            "Error: Invalid pattern `secs.x` for `Duration`, expected \
+             `human`, `secs`, `secs.N`, `millis` or `iso8601`"
+        );
+        assert!(format(&elapsed, "x:human.2").is_err());
+        assert_eq!(format(&elapsed, "x:secs.1000").unwrap().len(), 1004);
+        assert_eq!(
+            format(&elapsed, "x:secs.4000000000")
+                .unwrap_err()
+                .to_string(),
+            "Error: Invalid pattern `secs.4000000000` for `Duration`, \
+             the number of decimal places exceeds the limit of 1000"
+        );
+        assert!(format(&elapsed, "x:secs.99999999999999999999999").is_err());
+    }
+
+    #[test]
+    fn byte_sizes() {
+        let size = ByteSize(1536 * 1024);
+        assert_eq!(format(&size, "x").unwrap(), "1.5 MiB");
+        assert_eq!(format(&size, "x:iec.2").unwrap(), "1.50 MiB");
+        assert_eq!(format(&size, "x:si").unwrap(), "1.6 MB");
+        assert_eq!(format(&size, "x:>10bytes").unwrap(), "   1572864");
+        assert_eq!(format(&ByteSize(999), "x:si").unwrap(), "999 B");
+        assert_eq!(format(&ByteSize(1000), "x:si.0").unwrap(), "1 kB");
+        assert_eq!(format(&ByteSize(u64::MAX), "x").unwrap(), "16.0 EiB");
+        assert!(format(&size, "x:kb").is_err());
+        assert!(format(&size, "x:iec.1001").is_err());
+        assert!(format(&size, "x:si.4000000000").is_err());
+        assert!(!ByteSize(0).is_truthy());
+    }
+}
@@ -1,2 +1,17 @@
 #![warn(clippy::pedantic)]
 #![allow(clippy::must_use_candidate)]
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use syn::parse_macro_input;
+
+/// Compile-time checks for `curly!` (and similar macros) with a literal format string.
+///
+/// This is an implementation detail of `curly!`, use that instead.
+#[doc(hidden)]
+#[proc_macro]
+pub fn __curly_literal(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as curly_codegen_impl::macros::CurlyInput);
+
+    curly_codegen_impl::macros::curly(input).into()
+}
@@ -5,6 +5,15 @@ use syn::DeriveInput;
 use syn::{Data, Field, Fields, Ident, Lit, Meta};
 
 /// Derive a Provider
+///
+/// # Panics
+///
+/// Panics if the input is not a struct with at least one named field, or
+/// if a `#[curly_rename]` attribute does not have a string literal.
+///
+/// Fields annotated with `#[curly_each]` are sequences of providers, which can only
+/// be used in loop sections (`{#each key}...{/each}`) and conditional sections.
+#[allow(clippy::too_many_lines)]
 pub fn provider(input: DeriveInput) -> TokenStream {
     let generated;
 
@@ -36,15 +45,22 @@ pub fn provider(input: DeriveInput) -> TokenStream {
     }
 
     let module_name = Ident::new(
-        &format!("__curly_internal_provider_implement_for_{}", struct_name),
+        &format!("__curly_internal_provider_implement_for_{struct_name}"),
         span,
     );
 
     let mut matches = quote! {};
-
-    if struct_fields_named.named.is_empty() {
-        panic!("Deriving a provider on a struct with no fields does nothing");
-    }
+    let mut write_matches = quote! {};
+    let mut truthy_matches = quote! {};
+    let mut each_matches = quote! {};
+    let mut safe_matches = quote! {};
+    let mut keys = Vec::new();
+    let mut sequence_keys = Vec::new();
+
+    assert!(
+        !struct_fields_named.named.is_empty(),
+        "Deriving a provider on a struct with no fields does nothing"
+    );
 
     for field in &struct_fields_named.named {
         let field_ident;
@@ -54,18 +70,84 @@ pub fn provider(input: DeriveInput) -> TokenStream {
             unreachable!();
         }
 
-        if !should_provide(&field) {
+        if !should_provide(field) {
             continue;
         }
 
-        let field_name = get_provided_name(&field).unwrap_or_else(|| field_ident.to_string());
+        let field_name = get_provided_name(field).unwrap_or_else(|| field_ident.to_string());
+
+        if is_sequence(field) {
+            truthy_matches.extend(quote! {
+                #field_name => ::std::result::Result::Ok((&self.#field_ident).into_iter().next().is_some()),
+            });
+            each_matches.extend(quote! {
+                #field_name => ::std::result::Result::Ok(::std::boxed::Box::new(
+                    (&self.#field_ident).into_iter().map(|item| item as &dyn curly::Provider)
+                )),
+            });
+            sequence_keys.push(field_name);
+            continue;
+        }
 
         let quoted = quote! {
             #field_name => self.#field_ident.curly_fmt(context),
         };
         matches.extend(quoted);
+        write_matches.extend(quote! {
+            #field_name => self.#field_ident.curly_write(context, out),
+        });
+        truthy_matches.extend(quote! {
+            #field_name => self.#field_ident.curly_is_truthy(),
+        });
+        safe_matches.extend(quote! {
+            #field_name => self.#field_ident.is_safe(),
+        });
+        keys.push(field_name);
     }
 
+    let invalid_key = quote! {
+        ::std::result::Result::Err(
+            curly::CurlyErrorKind::Generic(
+                curly::CurlyError::from_boxed(
+                    format!("Invalid format specifier `{}`", key)
+                )
+            )
+        )
+    };
+
+    let provide_each = if each_matches.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            fn provide_each(&self, key: &str) -> curly::CurlyResult<curly::ProviderIter<'_>> {
+                match key {
+                    #each_matches
+                    _ => ::std::result::Result::Err(
+                        curly::CurlyErrorKind::Generic(
+                            curly::CurlyError::from_boxed(
+                                format!("Cannot iterate over `{}`", key)
+                            )
+                        )
+                    )
+                }
+            }
+        }
+    };
+
+    // Sequences can't be provided, so they are only in `SEQUENCE_KEYS`.
+    let (contains_sequence_key, sequence_keys) = if sequence_keys.is_empty() {
+        (quote! {}, quote! {})
+    } else {
+        (
+            quote! {
+                || curly::__private::contains_key(<Self as curly::ProviderSchema>::SEQUENCE_KEYS, key)
+            },
+            quote! {
+                const SEQUENCE_KEYS: &'static [&'static str] = &[#(#sequence_keys),*];
+            },
+        )
+    };
+
     generated = quote! {
         #[doc(hidden)] mod #module_name {
             extern crate #crate_ident as curly;
@@ -78,16 +160,45 @@ pub fn provider(input: DeriveInput) -> TokenStream {
                 fn provide(&self, context: &curly::formatting::CurlyContext, key: &str) -> curly::CurlyFmtResult {
                     match key {
                         #matches
-                        _ => ::std::result::Result::Err(
-                            curly::CurlyErrorKind::Generic(
-                                curly::CurlyError::from_boxed(
-                                    format!("Invalid format specifier `{}`.", key)
-                                )
-                            )
-                        )
+                        _ => #invalid_key
+                    }
+                }
+
+                fn provide_to(&self, context: &curly::formatting::CurlyContext, key: &str, out: &mut dyn ::std::fmt::Write) -> curly::CurlyResult<()> {
+                    match key {
+                        #write_matches
+                        _ => #invalid_key
+                    }
+                }
+
+                fn is_truthy(&self, key: &str) -> curly::CurlyResult<bool> {
+                    match key {
+                        #truthy_matches
+                        _ => #invalid_key
+                    }
+                }
+
+                #provide_each
+
+                fn contains_key(&self, key: &str) -> bool {
+                    curly::__private::contains_key(<Self as curly::ProviderSchema>::KEYS, key)
+                        #contains_sequence_key
+                }
+
+                fn is_safe(&self, key: &str) -> bool {
+                    match key {
+                        #safe_matches
+                        _ => false
                     }
                 }
             }
+
+            impl #impl_generics curly::ProviderSchema for super::#struct_name #ty_generics
+                #where_clause
+            {
+                const KEYS: &'static [&'static str] = &[#(#keys),*];
+                #sequence_keys
+            }
         }
     };
 
@@ -105,36 +216,41 @@ fn should_provide(field: &Field) -> bool {
         }
     }
 
-    for attr in &field.attrs {
-        if let Ok(meta) = attr.parse_meta() {
-            if let Meta::Path(path) = meta {
-                return !path.is_ident(&Ident::new("curly_ignore", Span::call_site()));
-            }
-        }
-    }
+    !has_attribute(field, "curly_ignore")
+}
 
-    true
+/// Whether or not this field is a sequence of providers,
+/// i.e. if the field is annotated with `#[curly_each]`.
+fn is_sequence(field: &Field) -> bool {
+    has_attribute(field, "curly_each")
+}
+
+/// Whether or not this field is annotated with `#[name]`.
+fn has_attribute(field: &Field, name: &str) -> bool {
+    field.attrs.iter().any(|attr| {
+        matches!(
+            attr.parse_meta(),
+            Ok(Meta::Path(path)) if path.is_ident(&Ident::new(name, Span::call_site()))
+        )
+    })
 }
 
 /// Get the provided name of a field if
 /// `#[curly_rename = "new_name"]` is specified.
 fn get_provided_name(field: &Field) -> Option<String> {
     for attr in &field.attrs {
-        if let Ok(meta) = attr.parse_meta() {
-            if let Meta::NameValue(meta) = meta {
-                if !meta
-                    .path
-                    .is_ident(&Ident::new("curly_rename", Span::call_site()))
-                {
-                    continue;
-                }
+        if let Ok(Meta::NameValue(meta)) = attr.parse_meta() {
+            if !meta
+                .path
+                .is_ident(&Ident::new("curly_rename", Span::call_site()))
+            {
+                continue;
+            }
 
-                if let Lit::Str(lit) = meta.lit {
-                    return Some(lit.value());
-                } else {
-                    panic!("Invalid literal for `#[curly_rename]`, must be a UTF-8 string literal");
-                }
+            if let Lit::Str(lit) = meta.lit {
+                return Some(lit.value());
             }
+            panic!("Invalid literal for `#[curly_rename]`, must be a UTF-8 string literal");
         }
     }
 
@@ -142,6 +258,7 @@ fn get_provided_name(field: &Field) -> Option<String> {
 }
 
 #[cfg(test)]
+#[allow(clippy::needless_raw_string_hashes)]
 mod tests {
 
     use super::*;
@@ -259,6 +376,47 @@ mod tests {
                             )
                         }
                     }
+
+                    fn provide_to(&self, context: &curly::formatting::CurlyContext, key: &str, out: &mut dyn ::std::fmt::Write) -> curly::CurlyResult<()> {
+                        match key {
+                            "some_field" => self.some_field.curly_write(context, out),
+                            _ => ::std::result::Result::Err(
+                                curly::CurlyErrorKind::Generic(
+                                    curly::CurlyError::from_boxed(
+                                        format!("Invalid format specifier `{}`", key)
+                                    )
+                                )
+                            )
+                        }
+                    }
+
+                    fn is_truthy(&self, key: &str) -> curly::CurlyResult<bool> {
+                        match key {
+                            "some_field" => self.some_field.curly_is_truthy(),
+                            _ => ::std::result::Result::Err(
+                                curly::CurlyErrorKind::Generic(
+                                    curly::CurlyError::from_boxed(
+                                        format!("Invalid format specifier `{}`", key)
+                                    )
+                                )
+                            )
+                        }
+                    }
+
+                    fn contains_key(&self, key: &str) -> bool {
+                        curly::__private::contains_key(<Self as curly::ProviderSchema>::KEYS, key)
+                    }
+
+                    fn is_safe(&self, key: &str) -> bool {
+                        match key {
+                            "some_field" => self.some_field.is_safe(),
+                            _ => false
+                        }
+                    }
+                }
+
+                impl curly::ProviderSchema for super::TestDerive {
+                    const KEYS: &'static [&'static str] = &["some_field"];
                 }
             }
         }.to_string();
@@ -300,6 +458,50 @@ mod tests {
                             )
                         }
                     }
+
+                    fn provide_to(&self, context: &curly::formatting::CurlyContext, key: &str, out: &mut dyn ::std::fmt::Write) -> curly::CurlyResult<()> {
+                        match key {
+                            "some_field" => self.some_field.curly_write(context, out),
+                            "other_field" => self.other_field.curly_write(context, out),
+                            _ => ::std::result::Result::Err(
+                                curly::CurlyErrorKind::Generic(
+                                    curly::CurlyError::from_boxed(
+                                        format!("Invalid format specifier `{}`", key)
+                                    )
+                                )
+                            )
+                        }
+                    }
+
+                    fn is_truthy(&self, key: &str) -> curly::CurlyResult<bool> {
+                        match key {
+                            "some_field" => self.some_field.curly_is_truthy(),
+                            "other_field" => self.other_field.curly_is_truthy(),
+                            _ => ::std::result::Result::Err(
+                                curly::CurlyErrorKind::Generic(
+                                    curly::CurlyError::from_boxed(
+                                        format!("Invalid format specifier `{}`", key)
+                                    )
+                                )
+                            )
+                        }
+                    }
+
+                    fn contains_key(&self, key: &str) -> bool {
+                        curly::__private::contains_key(<Self as curly::ProviderSchema>::KEYS, key)
+                    }
+
+                    fn is_safe(&self, key: &str) -> bool {
+                        match key {
+                            "some_field" => self.some_field.is_safe(),
+                            "other_field" => self.other_field.is_safe(),
+                            _ => false
+                        }
+                    }
+                }
+
+                impl curly::ProviderSchema for super::TestDerive {
+                    const KEYS: &'static [&'static str] = &["some_field", "other_field"];
                 }
             }
         }.to_string();
@@ -342,6 +544,47 @@ mod tests {
                             )
                         }
                     }
+
+                    fn provide_to(&self, context: &curly::formatting::CurlyContext, key: &str, out: &mut dyn ::std::fmt::Write) -> curly::CurlyResult<()> {
+                        match key {
+                            "some_field" => self.some_field.curly_write(context, out),
+                            _ => ::std::result::Result::Err(
+                                curly::CurlyErrorKind::Generic(
+                                    curly::CurlyError::from_boxed(
+                                        format!("Invalid format specifier `{}`", key)
+                                    )
+                                )
+                            )
+                        }
+                    }
+
+                    fn is_truthy(&self, key: &str) -> curly::CurlyResult<bool> {
+                        match key {
+                            "some_field" => self.some_field.curly_is_truthy(),
+                            _ => ::std::result::Result::Err(
+                                curly::CurlyErrorKind::Generic(
+                                    curly::CurlyError::from_boxed(
+                                        format!("Invalid format specifier `{}`", key)
+                                    )
+                                )
+                            )
+                        }
+                    }
+
+                    fn contains_key(&self, key: &str) -> bool {
+                        curly::__private::contains_key(<Self as curly::ProviderSchema>::KEYS, key)
+                    }
+
+                    fn is_safe(&self, key: &str) -> bool {
+                        match key {
+                            "some_field" => self.some_field.is_safe(),
+                            _ => false
+                        }
+                    }
+                }
+
+                impl curly::ProviderSchema for super::TestDerive {
+                    const KEYS: &'static [&'static str] = &["some_field"];
                 }
             }
         }.to_string();
@@ -382,6 +625,47 @@ mod tests {
                             )
                         }
                     }
+
+                    fn provide_to(&self, context: &curly::formatting::CurlyContext, key: &str, out: &mut dyn ::std::fmt::Write) -> curly::CurlyResult<()> {
+                        match key {
+                            "other_field" => self.some_field.curly_write(context, out),
+                            _ => ::std::result::Result::Err(
+                                curly::CurlyErrorKind::Generic(
+                                    curly::CurlyError::from_boxed(
+                                        format!("Invalid format specifier `{}`", key)
+                                    )
+                                )
+                            )
+                        }
+                    }
+
+                    fn is_truthy(&self, key: &str) -> curly::CurlyResult<bool> {
+                        match key {
+                            "other_field" => self.some_field.curly_is_truthy(),
+                            _ => ::std::result::Result::Err(
+                                curly::CurlyErrorKind::Generic(
+                                    curly::CurlyError::from_boxed(
+                                        format!("Invalid format specifier `{}`", key)
+                                    )
+                                )
+                            )
+                        }
+                    }
+
+                    fn contains_key(&self, key: &str) -> bool {
+                        curly::__private::contains_key(<Self as curly::ProviderSchema>::KEYS, key)
+                    }
+
+                    fn is_safe(&self, key: &str) -> bool {
+                        match key {
+                            "other_field" => self.some_field.is_safe(),
+                            _ => false
+                        }
+                    }
+                }
+
+                impl curly::ProviderSchema for super::TestDerive {
+                    const KEYS: &'static [&'static str] = &["other_field"];
                 }
             }
         }.to_string();
@@ -423,6 +707,149 @@ mod tests {
                             )
                         }
                     }
+
+                    fn provide_to(&self, context: &curly::formatting::CurlyContext, key: &str, out: &mut dyn ::std::fmt::Write) -> curly::CurlyResult<()> {
+                        match key {
+                            "some_field" => self.some_field.curly_write(context, out),
+                            _ => ::std::result::Result::Err(
+                                curly::CurlyErrorKind::Generic(
+                                    curly::CurlyError::from_boxed(
+                                        format!("Invalid format specifier `{}`", key)
+                                    )
+                                )
+                            )
+                        }
+                    }
+
+                    fn is_truthy(&self, key: &str) -> curly::CurlyResult<bool> {
+                        match key {
+                            "some_field" => self.some_field.curly_is_truthy(),
+                            _ => ::std::result::Result::Err(
+                                curly::CurlyErrorKind::Generic(
+                                    curly::CurlyError::from_boxed(
+                                        format!("Invalid format specifier `{}`", key)
+                                    )
+                                )
+                            )
+                        }
+                    }
+
+                    fn contains_key(&self, key: &str) -> bool {
+                        curly::__private::contains_key(<Self as curly::ProviderSchema>::KEYS, key)
+                    }
+
+                    fn is_safe(&self, key: &str) -> bool {
+                        match key {
+                            "some_field" => self.some_field.is_safe(),
+                            _ => false
+                        }
+                    }
+                }
+
+                impl<T> curly::ProviderSchema for super::TestDerive<T>
+                    where T: PartialEq
+                {
+                    const KEYS: &'static [&'static str] = &["some_field"];
+                }
+            }
+        }.to_string();
+
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn derive_each() {
+        let input: DeriveInput = syn::parse_str(
+            r"
+                struct TestDerive {
+                    some_field: String,
+                    #[curly_each]
+                    items: Vec<Item>,
+                }
+            ",
+        )
+        .unwrap();
+
+        let found = provider(input).to_string();
+
+        let expected = quote! {
+            #[doc(hidden)] mod __curly_internal_provider_implement_for_TestDerive {
+                extern crate curly as curly;
+
+                use curly::formatting::CurlyFmt;
+
+                impl curly::Provider for super::TestDerive {
+                    fn provide(&self, context: &curly::formatting::CurlyContext, key: &str) -> curly::CurlyFmtResult {
+                        match key {
+                            "some_field" => self.some_field.curly_fmt(context),
+                            _ => ::std::result::Result::Err(
+                                curly::CurlyErrorKind::Generic(
+                                    curly::CurlyError::from_boxed(
+                                        format!("Invalid format specifier `{}`", key)
+                                    )
+                                )
+                            )
+                        }
+                    }
+
+                    fn provide_to(&self, context: &curly::formatting::CurlyContext, key: &str, out: &mut dyn ::std::fmt::Write) -> curly::CurlyResult<()> {
+                        match key {
+                            "some_field" => self.some_field.curly_write(context, out),
+                            _ => ::std::result::Result::Err(
+                                curly::CurlyErrorKind::Generic(
+                                    curly::CurlyError::from_boxed(
+                                        format!("Invalid format specifier `{}`", key)
+                                    )
+                                )
+                            )
+                        }
+                    }
+
+                    fn is_truthy(&self, key: &str) -> curly::CurlyResult<bool> {
+                        match key {
+                            "some_field" => self.some_field.curly_is_truthy(),
+                            "items" => ::std::result::Result::Ok((&self.items).into_iter().next().is_some()),
+                            _ => ::std::result::Result::Err(
+                                curly::CurlyErrorKind::Generic(
+                                    curly::CurlyError::from_boxed(
+                                        format!("Invalid format specifier `{}`", key)
+                                    )
+                                )
+                            )
+                        }
+                    }
+
+                    fn provide_each(&self, key: &str) -> curly::CurlyResult<curly::ProviderIter<'_>> {
+                        match key {
+                            "items" => ::std::result::Result::Ok(::std::boxed::Box::new(
+                                (&self.items).into_iter().map(|item| item as &dyn curly::Provider)
+                            )),
+                            _ => ::std::result::Result::Err(
+                                curly::CurlyErrorKind::Generic(
+                                    curly::CurlyError::from_boxed(
+                                        format!("Cannot iterate over `{}`", key)
+                                    )
+                                )
+                            )
+                        }
+                    }
+
+                    fn contains_key(&self, key: &str) -> bool {
+                        curly::__private::contains_key(<Self as curly::ProviderSchema>::KEYS, key)
+                            || curly::__private::contains_key(<Self as curly::ProviderSchema>::SEQUENCE_KEYS, key)
+                    }
+
+                    fn is_safe(&self, key: &str) -> bool {
+                        match key {
+                            "some_field" => self.some_field.is_safe(),
+                            _ => false
+                        }
+                    }
+                }
+
+                impl curly::ProviderSchema for super::TestDerive {
+                    const KEYS: &'static [&'static str] = &["some_field"];
+                    const SEQUENCE_KEYS: &'static [&'static str] = &["items"];
                 }
             }
         }.to_string();
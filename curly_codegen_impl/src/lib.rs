@@ -4,3 +4,7 @@
 /// `#[derive(Provider)]` codegen
 #[cfg(feature = "derive")]
 pub mod derive;
+
+/// `curly!` codegen
+#[cfg(feature = "macros")]
+pub mod macros;
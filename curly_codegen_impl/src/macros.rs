@@ -0,0 +1,272 @@
+use curly_parser::Segment;
+use proc_macro2::{TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::{Expr, Ident, LitStr, Token, Type};
+
+/// The input to `curly!` when the format string is a string literal.
+///
+/// Arguments are either named (`name: Type = value`), positional (`value`),
+/// or the delegate provider (`..delegate: Type`), which must be last.
+///
+/// This is the same syntax as the runtime `curly!` macro, but with the path
+/// to the `curly` crate prepended (`$crate`), as procedural macros cannot
+/// use `$crate` themselves.
+pub struct CurlyInput {
+    krate: TokenTree,
+    format_string: LitStr,
+    arguments: Vec<Ident>,
+    positional: usize,
+    delegate_type: Option<Type>,
+}
+
+impl Parse for CurlyInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let krate = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let format_string = input.parse()?;
+
+        let mut arguments = Vec::new();
+        let mut positional = 0;
+        let mut delegate_type = None;
+
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+
+            if delegate_type.is_some() {
+                return Err(input.error("The delegate provider must be the last argument"));
+            }
+
+            if input.peek(Token![..]) {
+                input.parse::<Token![..]>()?;
+                input.parse::<Ident>()?;
+                input.parse::<Token![:]>()?;
+                delegate_type = Some(input.parse()?);
+            } else if input.peek(Ident) && input.peek2(Token![:]) && !input.peek2(Token![::]) {
+                arguments.push(input.parse()?);
+                input.parse::<Token![:]>()?;
+                input.parse::<Type>()?;
+                input.parse::<Token![=]>()?;
+                input.parse::<Expr>()?;
+            } else {
+                input.parse::<Expr>()?;
+                positional += 1;
+            }
+        }
+
+        Ok(Self {
+            krate,
+            format_string,
+            arguments,
+            positional,
+            delegate_type,
+        })
+    }
+}
+
+/// Generate compile-time checks for `curly!` (and similar macros) with a literal format string.
+///
+/// The format string is parsed at compile time, and every key is checked
+/// against the named arguments. Keys that are not named arguments are checked
+/// against the delegate provider's `ProviderSchema` with a constant assertion
+/// (if it implements it, as otherwise its keys are only known when formatting),
+/// or cause a compile error if there is no delegate provider. Keys that are only
+/// used by loop and conditional sections can also be sequence keys.
+///
+/// The generated code only contains items, so it can be used as a statement.
+pub fn curly(input: CurlyInput) -> TokenStream {
+    let CurlyInput {
+        krate,
+        format_string,
+        arguments,
+        positional,
+        delegate_type,
+    } = input;
+
+    let segments = match curly_parser::parse(&format_string.value()) {
+        Ok(segments) => segments,
+        Err(error) => {
+            return syn::Error::new(
+                format_string.span(),
+                format!("Invalid format string: {error}"),
+            )
+            .to_compile_error()
+        }
+    };
+
+    let keys = curly_parser::keys(&segments);
+    let mut section_keys = Vec::new();
+    collect_section_keys(&segments, &mut section_keys);
+
+    let mut checked = Vec::new();
+    let mut checks = quote! {};
+
+    for &key in &keys {
+        let is_positional = key.parse::<usize>().is_ok_and(|index| index < positional);
+        if is_positional
+            || checked.contains(&key)
+            || arguments.iter().any(|argument| argument == key)
+        {
+            continue;
+        }
+
+        if let Some(delegate_type) = &delegate_type {
+            let message = format!(
+                "Invalid format specifier `{key}`: not provided by `{}`",
+                delegate_type.to_token_stream()
+            );
+            let uses = keys.iter().filter(|&&other| other == key).count();
+            let section_uses = section_keys.iter().filter(|&&other| other == key).count();
+            let contains_sequence_key = if uses == section_uses {
+                quote! {
+                    || #krate::__private::schema_contains_key(
+                        #krate::__private::Schema::<#delegate_type>::SEQUENCE_KEYS,
+                        #key
+                    )
+                }
+            } else {
+                quote! {}
+            };
+            checks.extend(quote! {
+                const _: () = {
+                    use #krate::__private::NoSchema as _;
+                    ::core::assert!(
+                        #krate::__private::schema_contains_key(
+                            #krate::__private::Schema::<#delegate_type>::KEYS,
+                            #key
+                        ) #contains_sequence_key,
+                        #message
+                    );
+                };
+            });
+        } else {
+            return syn::Error::new(
+                format_string.span(),
+                format!("Invalid format specifier `{key}`"),
+            )
+            .to_compile_error();
+        }
+
+        checked.push(key);
+    }
+
+    checks
+}
+
+/// Collect the keys of loop sections and the conditions of conditional sections,
+/// which are the only keys that can be sequences, like [`curly_parser::keys`].
+fn collect_section_keys<'a>(segments: &'a [Segment], keys: &mut Vec<&'a str>) {
+    for segment in segments {
+        match segment {
+            Segment::Literal(_) | Segment::Placeholder(_) => {}
+            Segment::Conditional(conditional) => {
+                for branch in &conditional.branches {
+                    keys.push(&branch.condition);
+                    collect_section_keys(&branch.body, keys);
+                }
+                if let Some(otherwise) = &conditional.otherwise {
+                    collect_section_keys(otherwise, keys);
+                }
+            }
+            Segment::Loop(each) => {
+                keys.push(&each.key);
+                if let Some(separator) = &each.separator {
+                    collect_section_keys(separator, keys);
+                }
+            }
+            Segment::Select(select) => {
+                for case in &select.cases {
+                    collect_section_keys(&case.body, keys);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: &str) -> String {
+        curly(syn::parse_str(input).unwrap()).to_string()
+    }
+
+    #[test]
+    fn named_arguments() {
+        let found = expand(r#"curly, "{a} {b:>5}", a: String = a, b: String = b"#);
+        assert!(found.is_empty());
+    }
+
+    #[test]
+    fn delegate_checks() {
+        let found = expand(r#"curly, "{a} {b} {b}", a: String = a, ..d: Delegate"#);
+        let expected = quote! {
+            const _: () = {
+                use curly::__private::NoSchema as _;
+                ::core::assert!(
+                    curly::__private::schema_contains_key(
+                        curly::__private::Schema::<Delegate>::KEYS,
+                        "b"
+                    ),
+                    "Invalid format specifier `b`: not provided by `Delegate`"
+                );
+            };
+        }
+        .to_string();
+
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn positional_arguments() {
+        let found = expand(r#"curly, "{} {name} {} {1} {0:>4}", a, b.c(), name: String = name"#);
+        assert!(found.is_empty());
+
+        let found = expand(r#"curly, "{} {} {}", a, path::to::b"#);
+        assert!(found.contains("Invalid format specifier `2`"));
+    }
+
+    #[test]
+    fn count_arguments() {
+        let found = expand(r#"curly, "{a:>width$.*}", 3, a: String = a, width: usize = 5"#);
+        assert!(found.is_empty());
+
+        let found = expand(r#"curly, "{a:>width$}", a: String = a"#);
+        assert!(found.contains("Invalid format specifier `width`"));
+    }
+
+    #[test]
+    fn conditional_keys() {
+        let found = expand(r#"curly, "{#if a}{a}{#else if b}-{/if}", a: String = a, b: bool = b"#);
+        assert!(found.is_empty());
+
+        let found = expand(r#"curly, "{#if a}{/if}{#if c}{/if}", a: String = a"#);
+        assert!(found.contains("Invalid format specifier `c`"));
+    }
+
+    #[test]
+    fn loop_keys() {
+        let found = expand(r#"curly, "{#each items as item}{item.name} {@index}{/each}", ..d: D"#);
+        assert!(found.contains(r#""items""#));
+        assert!(!found.contains("name") && !found.contains("@index"));
+        assert!(found.contains("SEQUENCE_KEYS"));
+
+        let found = expand(r#"curly, "{#if items}{items}{/if}", ..d: D"#);
+        assert!(!found.contains("SEQUENCE_KEYS"));
+    }
+
+    #[test]
+    fn unknown_key() {
+        let found = expand(r#"curly, "{a} {c}", a: String = a"#);
+        assert!(found.contains("Invalid format specifier `c`"));
+    }
+
+    #[test]
+    fn invalid_format_string() {
+        let found = expand(r#"curly, "{a:.}", a: String = a"#);
+        assert!(found.contains("Invalid format string: Expected precision after `.` at 1:4"));
+    }
+}
@@ -51,7 +51,32 @@ use syn::{parse_macro_input, DeriveInput};
 ///
 /// In this example, the field `the_meaning_of_life_the_universe_and_everything` is renamed to `meaning_of_life`
 /// within the provider.
-#[proc_macro_derive(Provider, attributes(curly_ignore, curly_rename))]
+///
+/// ## `#[curly_each]`
+/// Put this on a struct field that is a sequence of providers (such as a `Vec` of structs
+/// that derive `Provider`) to iterate over it with `{#each key}...{/each}`.
+///
+/// ### Example
+///
+/// ```rs
+/// #[macro_use]
+/// extern crate curly;
+///
+/// #[derive(Provider)]
+/// struct LineItem {
+///     name: String,
+///     price: u32,
+/// }
+///
+/// #[derive(Provider)]
+/// struct Order {
+///     #[curly_each]
+///     items: Vec<LineItem>,
+/// }
+/// ```
+///
+/// In this example, `{#each items}{name}: {price}{#sep}, {/each}` lists every item of the order.
+#[proc_macro_derive(Provider, attributes(curly_ignore, curly_rename, curly_each))]
 pub fn provider_derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
 
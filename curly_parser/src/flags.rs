@@ -0,0 +1,169 @@
+/// The number display type for a single format segment.
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
+pub enum NumberType {
+    /// Octal (`o`) ⇒ [`Octal`](std::fmt::Octal)
+    Octal,
+    /// Lowercase hexadecimal (`x`) ⇒ [`LowerHex`](std::fmt::LowerHex)
+    LowerHex,
+    /// Uppercase hexadecimal (`X`) ⇒ [`UpperHex`](std::fmt::UpperHex)
+    UpperHex,
+    /// Show as location in memory, usually as hex (`p`) ⇒ [`Pointer`](std::fmt::Pointer)
+    Pointer,
+    /// Binary (`b`) ⇒ [`Binary`](std::fmt::Binary)
+    Binary,
+    /// Lowercase scientific notation (`e`) ⇒ [`LowerExp`](std::fmt::LowerExp)
+    LowerExp,
+    /// Uppercase scientific notation (`E`) ⇒ [`UpperExp`](std::fmt::UpperExp)
+    UpperExp,
+    /// Normal number types
+    Normal,
+}
+
+impl NumberType {
+    /// Get the number type for a type character, e.g. `x` ⇒ [`LowerHex`](NumberType::LowerHex)
+    pub fn from_char(ch: char) -> Option<Self> {
+        match ch {
+            'o' => Some(NumberType::Octal),
+            'x' => Some(NumberType::LowerHex),
+            'X' => Some(NumberType::UpperHex),
+            'p' => Some(NumberType::Pointer),
+            'b' => Some(NumberType::Binary),
+            'e' => Some(NumberType::LowerExp),
+            'E' => Some(NumberType::UpperExp),
+            _ => None,
+        }
+    }
+}
+
+/// The sign for a single format segment (`+` or `-`). `-` is currently unimplemented, but it may be used in the future.
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
+pub enum Sign {
+    /// Always print sign
+    Plus,
+    /// Unused, but may be used in the future with [`format!`](std::format!), so it's reserved here.
+    Minus,
+}
+
+/// The padding alignment for a single format segment.
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
+pub enum Alignment {
+    /// Left-aligned padding (`<`)
+    Left,
+    /// Right-aligned padding (`>`)
+    Right,
+    /// Center-aligned padding (`^`)
+    Center,
+}
+
+impl Alignment {
+    /// Get the alignment for an alignment character, e.g. `<` ⇒ [`Left`](Alignment::Left)
+    pub fn from_char(ch: char) -> Option<Self> {
+        match ch {
+            '<' => Some(Alignment::Left),
+            '>' => Some(Alignment::Right),
+            '^' => Some(Alignment::Center),
+            _ => None,
+        }
+    }
+}
+
+/// The digit grouping of a number (`{n:,}` or `{n:_}`).
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
+pub enum Grouping {
+    /// Groups digits with the group separator of the locale (`,`)
+    Locale,
+    /// Groups digits with underscores (`_`)
+    Underscore,
+}
+
+impl Grouping {
+    /// Get the grouping for a grouping character, e.g. `_` ⇒ [`Underscore`](Grouping::Underscore)
+    pub fn from_char(ch: char) -> Option<Self> {
+        match ch {
+            ',' => Some(Grouping::Locale),
+            '_' => Some(Grouping::Underscore),
+            _ => None,
+        }
+    }
+}
+
+/// Flags for a single format segment, e.g. padding,
+/// precision, number type, etc...
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+pub struct CurlyFlags {
+    /// The padding fill character (default: `' '`)
+    pub fill: char,
+    /// The padding alignment
+    pub align: Option<Alignment>,
+    /// The padding width
+    pub width: Option<usize>,
+    /// The key of the argument to take the padding width from (`name$` or `N$`).
+    ///
+    /// This is resolved into [`width`](CurlyFlags::width) when rendering.
+    pub width_argument: Option<String>,
+    /// The digit grouping of numbers
+    pub grouping: Option<Grouping>,
+    /// The float precision
+    pub precision: Option<usize>,
+    /// The key of the argument to take the precision from (`name$`, `N$` or `*`).
+    ///
+    /// This is resolved into [`precision`](CurlyFlags::precision) when rendering.
+    pub precision_argument: Option<String>,
+    /// The decimal sign
+    pub sign: Option<Sign>,
+    /// Whether the zero (`0`) flag is set or not
+    pub sign_aware_zero_pad: bool,
+    /// Whether the alternate (`#`) flag is set or not
+    pub alternate: bool,
+    /// The type of number (Normal, Octal, Lower/Upper Hex, etc...)
+    pub number_type: NumberType,
+}
+
+impl Default for CurlyFlags {
+    fn default() -> Self {
+        Self {
+            fill: ' ',
+            align: None,
+            width: None,
+            width_argument: None,
+            grouping: None,
+            precision: None,
+            precision_argument: None,
+            sign: None,
+            sign_aware_zero_pad: false,
+            alternate: false,
+            number_type: NumberType::Normal,
+        }
+    }
+}
+
+/// Custom flags, parsed from the flags that remain after the standard flags of a format segment.
+///
+/// The grammar is `[pattern] [@name[=value]]...`: free-form text such as a date format
+/// (`{date:%Y-%m-%d}`), followed by named flags, which start with `@` at the beginning
+/// of the custom flags or after whitespace (`{n:@currency=EUR @short}`).
+/// Values end at whitespace, unless they're quoted (`@sep=", "`).
+#[derive(Debug, Eq, PartialEq, Clone, Hash, Default)]
+pub struct CustomFlags {
+    /// The free-form text before any named flags, trimmed, if there is any.
+    pub pattern: Option<String>,
+    /// The named flags and their values, in order. Switches (`@short`) don't have a value.
+    pub named: Vec<(String, Option<String>)>,
+}
+
+impl CustomFlags {
+    /// Get the named flag `name`, or `None` if it's not set.
+    ///
+    /// The inner option is `None` for switches, which don't have a value.
+    pub fn get(&self, name: &str) -> Option<Option<&str>> {
+        self.named
+            .iter()
+            .find(|(flag, _)| flag == name)
+            .map(|(_, value)| value.as_deref())
+    }
+
+    /// Whether the named flag `name` is set, with or without a value.
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+}
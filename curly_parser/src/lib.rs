@@ -0,0 +1,1857 @@
+#![warn(clippy::pedantic)]
+#![allow(clippy::must_use_candidate)]
+
+//! The format string parser for Curly.
+//!
+//! This is kept separate from `curly` so that the same parser can be used both
+//! at runtime and at compile time (by the `curly!` procedural macro).
+
+pub mod flags;
+
+use flags::{Alignment, CurlyFlags, CustomFlags, Grouping, NumberType, Sign};
+
+/// A byte range within a format string.
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash, Default)]
+pub struct Span {
+    /// The byte offset of the start of this span (inclusive)
+    pub start: usize,
+    /// The byte offset of the end of this span (exclusive)
+    pub end: usize,
+}
+
+impl Span {
+    /// Create a new span from `start` to `end`.
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+}
+
+/// A syntax error within a format string.
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+pub struct ParseError {
+    message: String,
+    span: Span,
+    row: usize,
+    col: usize,
+}
+
+impl ParseError {
+    /// The message of this error, without position information.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The span of the format string that this error occurred in.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// The row (line) that this error occurred on, starting at 1.
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    /// The column that this error occurred on, starting at 1.
+    pub fn col(&self) -> usize {
+        self.col
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(fmt, "{} at {}:{}", self.message, self.row, self.col)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// A single parsed format segment (one statement between `{}`s).
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+pub struct Placeholder {
+    /// The key to look up in the provider.
+    ///
+    /// Positional arguments (`{}` and `{0}`) use their index as their key,
+    /// so `"{} {name} {}"` has the keys `0`, `name` and `1`.
+    pub key: String,
+    /// Flags that are parsed by Curly for things such as padding,
+    /// format type, etc...
+    pub flags: CurlyFlags,
+    /// Custom flags (remaining flags that are unparsed by Curly
+    /// so that they can be used for custom formatting behaviours.
+    pub custom_flags: Option<String>,
+    /// The custom flags, parsed into a pattern and named flags.
+    pub custom: CustomFlags,
+    /// The filters to apply to the formatted value, in order (`{name | upper | truncate:20}`).
+    pub filters: Vec<FilterCall>,
+    /// The span of this segment within the format string.
+    pub span: Span,
+}
+
+/// A filter applied to a placeholder, e.g. `truncate:20,...`.
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+pub struct FilterCall {
+    /// The name of the filter.
+    pub name: String,
+    /// The arguments of the filter, separated by `,` after a `:`.
+    ///
+    /// Arguments are trimmed, unless they're quoted (`"a, b"`).
+    pub arguments: Vec<String>,
+    /// The span of this filter within the format string.
+    pub span: Span,
+}
+
+/// A piece of a parsed format string.
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+pub enum Segment {
+    /// Literal text, with `{{` and `}}` already unescaped.
+    Literal(String),
+    /// A format segment to be filled in by a provider.
+    Placeholder(Placeholder),
+    /// A conditional section (`{#if key}...{/if}`).
+    Conditional(Conditional),
+    /// A loop section (`{#each key}...{/each}`).
+    Loop(Loop),
+    /// A select section (`{count, plural, one {# item} other {# items}}`).
+    Select(Select),
+}
+
+/// A conditional section, `{#if key}...{#else if key}...{#else}...{/if}`.
+///
+/// The body of the first branch whose condition is truthy is rendered, or the
+/// `{#else}` body if there is no such branch.
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+pub struct Conditional {
+    /// The `{#if}` branch, followed by any `{#else if}` branches.
+    pub branches: Vec<Branch>,
+    /// The body of the `{#else}` branch, if there is one.
+    pub otherwise: Option<Vec<Segment>>,
+    /// The span of the opening `{#if}` tag within the format string.
+    pub span: Span,
+}
+
+/// A single branch of a [`Conditional`](Conditional).
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+pub struct Branch {
+    /// The key whose truthiness decides whether this branch is rendered.
+    pub condition: String,
+    /// The segments to render if the condition is truthy.
+    pub body: Vec<Segment>,
+    /// The span of the `{#if}` or `{#else if}` tag within the format string.
+    pub span: Span,
+}
+
+/// A loop section, `{#each key as item}...{#sep}...{/each}`.
+///
+/// The body is rendered once for every item of the sequence `key`, with the item's keys
+/// in scope (also as `item.key` if there's a binding), as well as `@index`, `@first` and `@last`.
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+pub struct Loop {
+    /// The key of the sequence to iterate over.
+    pub key: String,
+    /// The name that the item is bound to with `as`, if any.
+    pub binding: Option<String>,
+    /// The segments to render for every item.
+    pub body: Vec<Segment>,
+    /// The segments of the `{#sep}` section, rendered between items, if any.
+    pub separator: Option<Vec<Segment>>,
+    /// The span of the opening `{#each}` tag within the format string.
+    pub span: Span,
+}
+
+/// A select section, which renders the case matching the value of its key, in the style of
+/// ICU `MessageFormat`: `{count, plural, =0 {no files} one {# file} other {# files}}` or
+/// `{role, select, admin {Administrator} other {User}}`.
+///
+/// Within the cases of a plural section, `#` is the value of its key. A case ends at the first
+/// unmatched `}`, so `}}` closes a case rather than being a literal `}`.
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+pub struct Select {
+    /// The key whose value selects the case.
+    pub key: String,
+    /// Whether cases are selected by plural category or by value.
+    pub kind: SelectKind,
+    /// The cases, in order, which always include an `other` case.
+    pub cases: Vec<Case>,
+    /// The span of the whole section within the format string.
+    pub span: Span,
+}
+
+/// The kind of a [`Select`](Select) section.
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
+pub enum SelectKind {
+    /// Cases are plural categories (`zero`, `one`, `two`, `few`, `many` and `other`),
+    /// or exact numbers (`=0`).
+    Plural,
+    /// Cases are the values of the key.
+    Select,
+}
+
+/// The plural categories that can be the case of a plural section.
+pub const PLURAL_CATEGORIES: &[&str] = &["zero", "one", "two", "few", "many", "other"];
+
+/// A single case of a [`Select`](Select) section.
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+pub struct Case {
+    /// The selector of this case, e.g. `one`, `=0` or `admin`.
+    pub selector: String,
+    /// The segments to render if this case is selected.
+    pub body: Vec<Segment>,
+    /// The span of the selector and the opening `{` within the format string.
+    pub span: Span,
+}
+
+/// The maximum depth of nested conditional, loop and select sections, beyond which a format
+/// string fails to parse rather than overflowing the stack.
+pub const MAX_NESTING: usize = 64;
+
+/// The variables that are in scope within a [`Loop`](Loop).
+pub const LOOP_VARIABLES: &[&str] = &["@index", "@first", "@last"];
+
+/// A block tag, e.g. `{#if key}` or `{/if}`.
+struct Tag {
+    kind: TagKind,
+    span: Span,
+}
+
+enum TagKind {
+    If(String),
+    ElseIf(String),
+    Else,
+    Each {
+        key: String,
+        binding: Option<String>,
+    },
+    Separator,
+    End(&'static str),
+    /// The `}` that closes a case of a select section.
+    Close,
+}
+
+/// Parse a whole format string into its literal and placeholder segments.
+///
+/// # Errors
+///
+/// Returns a [`ParseError`](ParseError) if the format string is malformed, e.g.
+/// an unmatched `{` or `}`, a missing key, or an invalid precision.
+pub fn parse(format_string: &str) -> Result<Vec<Segment>, ParseError> {
+    Parser::new(format_string, 1, 1).parse_template()
+}
+
+/// The keys that a provider needs to provide to render `segments`, in order of appearance.
+///
+/// This includes the keys of width and precision arguments (`name$`), which
+/// come before the key of their format segment, and the conditions of
+/// conditional sections. Keys may appear more than once.
+///
+/// Keys within the body of a loop section are resolved against each item first,
+/// so only the key of the sequence (and of the separator) is included.
+pub fn keys(segments: &[Segment]) -> Vec<&str> {
+    let mut keys = Vec::new();
+    collect_keys(segments, &mut keys);
+    keys
+}
+
+fn collect_keys<'a>(segments: &'a [Segment], keys: &mut Vec<&'a str>) {
+    for segment in segments {
+        match segment {
+            Segment::Literal(_) => {}
+            Segment::Placeholder(placeholder) => {
+                keys.extend(placeholder.flags.width_argument.as_deref());
+                keys.extend(placeholder.flags.precision_argument.as_deref());
+                keys.push(&placeholder.key);
+            }
+            Segment::Conditional(conditional) => {
+                for branch in &conditional.branches {
+                    keys.push(&branch.condition);
+                    collect_keys(&branch.body, keys);
+                }
+                if let Some(otherwise) = &conditional.otherwise {
+                    collect_keys(otherwise, keys);
+                }
+            }
+            Segment::Loop(each) => {
+                keys.push(&each.key);
+                if let Some(separator) = &each.separator {
+                    collect_keys(separator, keys);
+                }
+            }
+            Segment::Select(select) => {
+                keys.push(&select.key);
+                for case in &select.cases {
+                    collect_keys(&case.body, keys);
+                }
+            }
+        }
+    }
+}
+
+/// Parse a single format segment (the text between a `{` and a `}`).
+///
+/// `base_row` and `base_col` are the position of the segment within its
+/// format string, and are only used for error reporting.
+///
+/// # Errors
+///
+/// Returns a [`ParseError`](ParseError) if the segment is malformed.
+pub fn parse_segment(
+    format_segment: &str,
+    base_row: usize,
+    base_col: usize,
+) -> Result<Placeholder, ParseError> {
+    let mut parser = Parser::new(format_segment, base_row, base_col);
+    parser.parse_placeholder(0, format_segment.len())
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+    base_row: usize,
+    base_col: usize,
+    /// The index of the next implicit positional argument (`{}`)
+    next_implicit: usize,
+    /// The number of select cases that are being parsed, which end at an unmatched `}`.
+    cases: usize,
+    /// The keys of the plural sections that are being parsed, the last of which is `#`.
+    plural_keys: Vec<String>,
+    /// The number of sections that are being parsed.
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(src: &'a str, base_row: usize, base_col: usize) -> Self {
+        Self {
+            src,
+            pos: 0,
+            base_row,
+            base_col,
+            next_implicit: 0,
+            cases: 0,
+            plural_keys: Vec::new(),
+            depth: 0,
+        }
+    }
+
+    /// Parse a nested section with `parse`, failing if it is nested deeper than [`MAX_NESTING`].
+    fn nested<T>(
+        &mut self,
+        span: Span,
+        parse: impl FnOnce(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<T, ParseError> {
+        if self.depth == MAX_NESTING {
+            return Err(self.error(
+                format!("Sections are nested more than {MAX_NESTING} levels deep"),
+                span,
+            ));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn error(&self, message: impl Into<String>, span: Span) -> ParseError {
+        let before = &self.src[..span.start];
+        let (row, col) = match before.rfind('\n') {
+            Some(idx) => (
+                self.base_row + before.matches('\n').count(),
+                before[idx + 1..].chars().count() + 1,
+            ),
+            None => (self.base_row, self.base_col + before.chars().count()),
+        };
+
+        ParseError {
+            message: message.into(),
+            span,
+            row,
+            col,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn peek_nth(&self, n: usize) -> Option<char> {
+        self.src[self.pos..].chars().nth(n)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.pos += ch.len_utf8();
+        Some(ch)
+    }
+
+    fn parse_template(&mut self) -> Result<Vec<Segment>, ParseError> {
+        let (segments, tag) = self.parse_body()?;
+        match tag {
+            None => Ok(segments),
+            Some(Tag {
+                kind: TagKind::End(name),
+                span,
+            }) => Err(self.error(
+                format!("Unbalanced `{{/{name}}}`: no matching `{{#{name}}}`"),
+                span,
+            )),
+            Some(Tag {
+                kind: TagKind::Separator,
+                span,
+            }) => Err(self.error(
+                format!("`{}` outside of an `{{#each}}` block", self.slice(span)),
+                span,
+            )),
+            Some(Tag { span, .. }) => Err(self.error(
+                format!("`{}` outside of an `{{#if}}` block", self.slice(span)),
+                span,
+            )),
+        }
+    }
+
+    /// Parse segments until the end of the format string, or until a block tag
+    /// that belongs to an enclosing block (such as `{#else}` or `{/if}`), which is returned.
+    fn parse_body(&mut self) -> Result<(Vec<Segment>, Option<Tag>), ParseError> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+
+        while let Some(ch) = self.peek() {
+            let start = self.pos;
+            match ch {
+                '{' if self.peek_nth(1) == Some('{') => {
+                    self.pos += 2;
+                    literal.push('{');
+                }
+                '}' if self.cases > 0 => {
+                    self.pos += 1;
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(literal));
+                    }
+                    let tag = Tag {
+                        kind: TagKind::Close,
+                        span: Span::new(start, self.pos),
+                    };
+                    return Ok((segments, Some(tag)));
+                }
+                '}' if self.peek_nth(1) == Some('}') => {
+                    self.pos += 2;
+                    literal.push('}');
+                }
+                '}' => {
+                    return Err(self.error(
+                        "Unmatched `}` (use `}}` for a literal `}`)",
+                        Span::new(start, start + 1),
+                    ))
+                }
+                '#' if !self.plural_keys.is_empty() => {
+                    self.bump();
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(self.plural_number(Span::new(start, self.pos)));
+                }
+                '{' => {
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    if let Some(select) = self.parse_select(start)? {
+                        segments.push(Segment::Select(select));
+                        continue;
+                    }
+
+                    let end = self.find_segment_end(start)?;
+
+                    let span = Span::new(start, end + 1);
+                    if matches!(self.peek_nth(1), Some('#' | '/')) {
+                        match self.parse_tag(start + 1, end)? {
+                            TagKind::If(condition) => {
+                                self.pos = end + 1;
+                                let conditional = self.nested(span, |parser| {
+                                    parser.parse_conditional(condition, span)
+                                })?;
+                                segments.push(Segment::Conditional(conditional));
+                            }
+                            TagKind::Each { key, binding } => {
+                                self.pos = end + 1;
+                                let each = self
+                                    .nested(span, |parser| parser.parse_loop(key, binding, span))?;
+                                segments.push(Segment::Loop(each));
+                            }
+                            kind => {
+                                self.pos = end + 1;
+                                return Ok((segments, Some(Tag { kind, span })));
+                            }
+                        }
+                    } else {
+                        let mut placeholder = self.parse_placeholder(start + 1, end)?;
+                        placeholder.span = span;
+                        segments.push(Segment::Placeholder(placeholder));
+                        self.pos = end + 1;
+                    }
+                }
+                _ => {
+                    self.bump();
+                    literal.push(ch);
+                }
+            }
+        }
+
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        Ok((segments, None))
+    }
+
+    /// Parse the branches of a conditional section, after its opening `{#if}` tag.
+    fn parse_conditional(
+        &mut self,
+        condition: String,
+        span: Span,
+    ) -> Result<Conditional, ParseError> {
+        let mut branch = (condition, span);
+        let mut branches = Vec::new();
+
+        loop {
+            let (body, tag) = self.parse_body()?;
+            let (condition, branch_span) = branch;
+            branches.push(Branch {
+                condition,
+                body,
+                span: branch_span,
+            });
+
+            match tag {
+                Some(Tag {
+                    kind: TagKind::ElseIf(condition),
+                    span: tag_span,
+                }) => branch = (condition, tag_span),
+                Some(Tag {
+                    kind: TagKind::Else,
+                    ..
+                }) => {
+                    let (otherwise, tag) = self.parse_body()?;
+                    self.expect_end("if", tag.as_ref(), span)?;
+                    return Ok(Conditional {
+                        branches,
+                        otherwise: Some(otherwise),
+                        span,
+                    });
+                }
+                tag => {
+                    self.expect_end("if", tag.as_ref(), span)?;
+                    return Ok(Conditional {
+                        branches,
+                        otherwise: None,
+                        span,
+                    });
+                }
+            }
+        }
+    }
+
+    /// The `#` at `span` within a case of a plural section, which is the value of its key.
+    fn plural_number(&self, span: Span) -> Segment {
+        Segment::Placeholder(Placeholder {
+            key: self.plural_keys.last().cloned().unwrap_or_default(),
+            flags: CurlyFlags::default(),
+            custom_flags: None,
+            custom: CustomFlags::default(),
+            filters: Vec::new(),
+            span,
+        })
+    }
+
+    /// Parse the select section opened at `start`, if there is one:
+    /// `{key, plural|select, selector {case}...}`.
+    fn parse_select(&mut self, start: usize) -> Result<Option<Select>, ParseError> {
+        let end = self.src.len();
+        self.pos = start + 1;
+        self.skip_whitespace(end);
+        let key = match self.parse_key(end) {
+            Ok(Some(key)) => key,
+            _ => String::new(),
+        };
+        self.skip_whitespace(end);
+        if key.is_empty() || self.peek() != Some(',') {
+            self.pos = start;
+            return Ok(None);
+        }
+        self.bump();
+
+        self.skip_whitespace(end);
+        let word_start = self.pos;
+        self.parse_identifier(end);
+        let kind = match &self.src[word_start..self.pos] {
+            "plural" => SelectKind::Plural,
+            "select" => SelectKind::Select,
+            word => {
+                return Err(self.error(
+                    format!("Expected `plural` or `select` after `{key},`, found `{word}`"),
+                    Span::new(word_start, self.pos),
+                ))
+            }
+        };
+        let header = Span::new(start, self.pos);
+        self.skip_whitespace(end);
+        if self.bump() != Some(',') {
+            return Err(self.error(
+                format!("Expected `,` after `{}`", self.slice(header)),
+                Span::new(header.end, self.pos),
+            ));
+        }
+
+        let mut cases = Vec::new();
+        loop {
+            self.skip_whitespace(end);
+            match self.peek() {
+                None => {
+                    return Err(
+                        self.error(format!("Unclosed `{}` section", self.slice(header)), header)
+                    )
+                }
+                Some('}') => {
+                    self.bump();
+                    break;
+                }
+                Some(_) => {
+                    cases.push(self.nested(header, |parser| parser.parse_case(&key, kind))?);
+                }
+            }
+        }
+
+        if !cases.iter().any(|case| case.selector == "other") {
+            return Err(self.error(
+                format!("Expected an `other` case in `{}`", self.slice(header)),
+                Span::new(start, self.pos),
+            ));
+        }
+
+        Ok(Some(Select {
+            key,
+            kind,
+            cases,
+            span: Span::new(start, self.pos),
+        }))
+    }
+
+    /// Parse a single case of a select section, e.g. `one {# file}`.
+    fn parse_case(&mut self, key: &str, kind: SelectKind) -> Result<Case, ParseError> {
+        let end = self.src.len();
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|ch| !ch.is_whitespace() && ch != '{' && ch != '}')
+        {
+            self.bump();
+        }
+        let selector = self.src[start..self.pos].to_string();
+
+        let valid = match kind {
+            SelectKind::Plural => {
+                PLURAL_CATEGORIES.contains(&selector.as_str())
+                    || selector
+                        .strip_prefix('=')
+                        .is_some_and(|number| number.parse::<f64>().is_ok())
+            }
+            SelectKind::Select => {
+                !selector.is_empty()
+                    && selector
+                        .chars()
+                        .all(|ch| ch == '_' || ch == '-' || ch.is_alphanumeric())
+            }
+        };
+        if !valid {
+            let found = match self.peek() {
+                _ if !selector.is_empty() => format!("`{selector}`"),
+                Some(ch) => format!("`{ch}`"),
+                None => String::from("end of format string"),
+            };
+            let expected = match kind {
+                SelectKind::Plural => "a plural category or `=number`",
+                SelectKind::Select => "a value",
+            };
+            return Err(self.error(
+                format!("Expected {expected} as a case of `{key}`, found {found}"),
+                Span::new(start, self.pos.max(start + 1).min(end)),
+            ));
+        }
+
+        self.skip_whitespace(end);
+        if self.peek() != Some('{') {
+            return Err(self.error(
+                format!("Expected `{{` after the case `{selector}`"),
+                Span::new(start, self.pos),
+            ));
+        }
+        self.bump();
+        let span = Span::new(start, self.pos);
+
+        self.cases += 1;
+        if kind == SelectKind::Plural {
+            self.plural_keys.push(key.to_string());
+        }
+        let body = self.parse_body();
+        if kind == SelectKind::Plural {
+            self.plural_keys.pop();
+        }
+        self.cases -= 1;
+
+        match body? {
+            (
+                body,
+                Some(Tag {
+                    kind: TagKind::Close,
+                    ..
+                }),
+            ) => Ok(Case {
+                selector,
+                body,
+                span,
+            }),
+            (_, Some(tag)) => Err(self.error(
+                format!(
+                    "Expected `}}` to close the case `{selector}`, found `{}`",
+                    self.slice(tag.span)
+                ),
+                tag.span,
+            )),
+            (_, None) => Err(self.error(format!("Unclosed case `{selector}`"), span)),
+        }
+    }
+
+    /// Parse the body and separator of a loop section, after its opening `{#each}` tag.
+    fn parse_loop(
+        &mut self,
+        key: String,
+        binding: Option<String>,
+        span: Span,
+    ) -> Result<Loop, ParseError> {
+        let (body, tag) = self.parse_body()?;
+        let separator = match tag {
+            Some(Tag {
+                kind: TagKind::Separator,
+                ..
+            }) => {
+                let (separator, tag) = self.parse_body()?;
+                self.expect_end("each", tag.as_ref(), span)?;
+                Some(separator)
+            }
+            tag => {
+                self.expect_end("each", tag.as_ref(), span)?;
+                None
+            }
+        };
+
+        Ok(Loop {
+            key,
+            binding,
+            body,
+            separator,
+            span,
+        })
+    }
+
+    /// Check that the body of the block opened at `open` ended with `{/name}`.
+    fn expect_end(&self, name: &str, tag: Option<&Tag>, open: Span) -> Result<(), ParseError> {
+        match tag {
+            Some(Tag {
+                kind: TagKind::End(end),
+                ..
+            }) if *end == name => Ok(()),
+            Some(tag) => Err(self.error(
+                format!("Expected `{{/{name}}}`, found `{}`", self.slice(tag.span)),
+                tag.span,
+            )),
+            None => Err(self.error(format!("Unclosed `{}` block", self.slice(open)), open)),
+        }
+    }
+
+    fn slice(&self, span: Span) -> &'a str {
+        &self.src[span.start..span.end]
+    }
+
+    /// Parse the block tag between `start` and `end`, exclusive of braces,
+    /// e.g. `#if key`, `#else if key`, `#else` or `/if`.
+    fn parse_tag(&mut self, start: usize, end: usize) -> Result<TagKind, ParseError> {
+        self.pos = start;
+        let sigil = self.bump();
+        let name = self.parse_tag_word(end);
+
+        let tag = match (sigil, name) {
+            (Some('#'), "if") => TagKind::If(self.parse_tag_key(end, "#if")?),
+            (Some('#'), "else") => {
+                self.skip_whitespace(end);
+                if self.pos == end {
+                    TagKind::Else
+                } else if self.parse_tag_word(end) == "if" {
+                    TagKind::ElseIf(self.parse_tag_key(end, "#else if")?)
+                } else {
+                    return Err(self.error(
+                        "Expected `if` or `}` after `#else`",
+                        Span::new(self.pos, end),
+                    ));
+                }
+            }
+            (Some('#'), "each") => {
+                let key = self.parse_tag_key(end, "#each")?;
+                let binding = match self.parse_tag_word(end) {
+                    "" => None,
+                    "as" => Some(self.parse_binding(end)?),
+                    word => {
+                        return Err(self.error(
+                            format!("Expected `as` or `}}`, found `{word}`"),
+                            Span::new(self.pos - word.len(), self.pos),
+                        ))
+                    }
+                };
+                TagKind::Each { key, binding }
+            }
+            (Some('#'), "sep") => TagKind::Separator,
+            (Some('/'), "if") => TagKind::End("if"),
+            (Some('/'), "each") => TagKind::End("each"),
+            _ => {
+                return Err(self.error(
+                    format!("Unknown block `{}`", &self.src[start..end]),
+                    Span::new(start - 1, end + 1),
+                ))
+            }
+        };
+
+        self.skip_whitespace(end);
+        if self.pos < end {
+            return Err(self.error(
+                format!("Unexpected `{}` in block tag", &self.src[self.pos..end]),
+                Span::new(self.pos, end),
+            ));
+        }
+
+        Ok(tag)
+    }
+
+    /// Parse the next whitespace-separated word of a block tag.
+    fn parse_tag_word(&mut self, end: usize) -> &'a str {
+        self.skip_whitespace(end);
+        let start = self.pos;
+        while self.pos < end && self.peek().is_some_and(|ch| !ch.is_whitespace()) {
+            self.bump();
+        }
+        &self.src[start..self.pos]
+    }
+
+    /// Parse the key of a block tag, such as the condition of `{#if key}`.
+    fn parse_tag_key(&mut self, end: usize, tag: &str) -> Result<String, ParseError> {
+        self.skip_whitespace(end);
+        let start = self.pos;
+        match self.parse_key(end)? {
+            Some(key) => Ok(key),
+            None => Err(self.error(
+                format!("Expected a key after `{tag}`"),
+                Span::new(start, start),
+            )),
+        }
+    }
+
+    /// Parse the name that the items of a loop are bound to, after `as`.
+    fn parse_binding(&mut self, end: usize) -> Result<String, ParseError> {
+        self.skip_whitespace(end);
+        let start = self.pos;
+        if self.parse_identifier(end) && self.peek() != Some('.') {
+            Ok(self.src[start..self.pos].to_string())
+        } else {
+            Err(self.error("Expected a name after `as`", Span::new(start, self.pos)))
+        }
+    }
+
+    fn skip_whitespace(&mut self, end: usize) {
+        while self.pos < end && self.peek().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+    }
+
+    /// Find the closing `}` of the segment opened at `start`.
+    fn find_segment_end(&self, start: usize) -> Result<usize, ParseError> {
+        for (idx, ch) in self.src[start + 1..].char_indices() {
+            let idx = start + 1 + idx;
+            match ch {
+                '}' => return Ok(idx),
+                '{' => return Err(self.error("Expected `}`, found `{`", Span::new(idx, idx + 1))),
+                _ => {}
+            }
+        }
+
+        Err(self.error(
+            "Expected `}` to close format segment",
+            Span::new(start, self.src.len()),
+        ))
+    }
+
+    /// Parse the segment between `start` and `end`, exclusive of braces.
+    fn parse_placeholder(&mut self, start: usize, end: usize) -> Result<Placeholder, ParseError> {
+        self.pos = start;
+        let key = self.parse_key(end)?;
+
+        let mut flags = CurlyFlags::default();
+        let mut custom_flags = None;
+        let mut custom = CustomFlags::default();
+
+        let pipe = self.find_pipe(end);
+        let flags_end = pipe.map_or(end, |pipe| {
+            self.pos + self.src[self.pos..pipe].trim_end().len()
+        });
+
+        match self.peek().filter(|_| self.pos < flags_end) {
+            None => {}
+            Some(':') => {
+                self.bump();
+                self.parse_standard_flags(&mut flags, flags_end)?;
+                if self.pos < flags_end {
+                    custom_flags = Some(self.src[self.pos..flags_end].to_string());
+                    custom = self.parse_custom_flags(flags_end)?;
+                }
+            }
+            Some(ch) => {
+                return Err(self.error(
+                    format!("Expected `:`, `|` or `}}`, found `{ch}`"),
+                    Span::new(self.pos, self.pos + ch.len_utf8()),
+                ))
+            }
+        }
+
+        let filters = match pipe {
+            Some(pipe) => self.parse_filters(pipe, end)?,
+            None => Vec::new(),
+        };
+
+        // Implicit positional arguments are numbered after any `.*` precision in the same segment,
+        // like `format!`, so `{:.*}` takes the precision and then the value.
+        let key = key.unwrap_or_else(|| self.next_implicit_index());
+
+        Ok(Placeholder {
+            key,
+            flags,
+            custom_flags,
+            custom,
+            filters,
+            span: Span::new(start, end),
+        })
+    }
+
+    /// Parse the custom flags of a segment, which end at `end`: `[pattern] [@name[=value]]...`
+    fn parse_custom_flags(&mut self, end: usize) -> Result<CustomFlags, ParseError> {
+        let start = self.pos;
+        let mut first_flag = None;
+        let mut previous = None;
+        for (idx, ch) in self.src[start..end].char_indices() {
+            if ch == '@' && previous.is_none_or(char::is_whitespace) {
+                first_flag = Some(start + idx);
+                break;
+            }
+            previous = Some(ch);
+        }
+
+        let pattern_end = first_flag.unwrap_or(end);
+        let pattern = self.src[start..pattern_end].trim();
+        let mut custom = CustomFlags {
+            pattern: Some(pattern.to_string()).filter(|pattern| !pattern.is_empty()),
+            named: Vec::new(),
+        };
+
+        self.pos = pattern_end;
+        while self.pos < end {
+            let flag_start = self.pos;
+            if self.bump() != Some('@') {
+                return Err(self.error(
+                    format!(
+                        "Expected `@` to start a custom flag, found `{}`",
+                        &self.src[flag_start..end].trim_end()
+                    ),
+                    Span::new(flag_start, end),
+                ));
+            }
+
+            let name_start = self.pos;
+            while self.pos < end
+                && self
+                    .peek()
+                    .is_some_and(|ch| ch == '_' || ch == '-' || ch.is_alphanumeric())
+            {
+                self.bump();
+            }
+            let name = &self.src[name_start..self.pos];
+            if name.is_empty() {
+                return Err(self.error(
+                    "Expected a custom flag name after `@`",
+                    Span::new(flag_start, self.pos),
+                ));
+            }
+            if custom.contains(name) {
+                return Err(self.error(
+                    format!("Duplicate custom flag `{name}`"),
+                    Span::new(flag_start, self.pos),
+                ));
+            }
+
+            let value = if self.peek().filter(|_| self.pos < end) == Some('=') {
+                self.bump();
+                Some(self.parse_custom_flag_value(name, end)?)
+            } else {
+                None
+            };
+
+            match self.peek().filter(|_| self.pos < end) {
+                None => {}
+                Some(ch) if ch.is_whitespace() => self.skip_whitespace(end),
+                Some(ch) => {
+                    return Err(self.error(
+                        format!("Unexpected `{ch}` in custom flag `{name}`"),
+                        Span::new(self.pos, self.pos + ch.len_utf8()),
+                    ))
+                }
+            }
+
+            custom.named.push((name.to_string(), value));
+        }
+
+        self.pos = end;
+        Ok(custom)
+    }
+
+    /// Parse the value of the custom flag `name`, after the `=`.
+    fn parse_custom_flag_value(&mut self, name: &str, end: usize) -> Result<String, ParseError> {
+        let start = self.pos;
+        if self.peek().filter(|_| self.pos < end) == Some('"') {
+            let quoted = &self.src[start + 1..end];
+            let len = quoted.find('"').ok_or_else(|| {
+                self.error(
+                    format!("Expected `\"` to close the value of custom flag `{name}`"),
+                    Span::new(start, end),
+                )
+            })?;
+            self.pos = start + len + 2;
+            return Ok(quoted[..len].to_string());
+        }
+
+        while self.pos < end && self.peek().is_some_and(|ch| !ch.is_whitespace()) {
+            self.bump();
+        }
+        if self.pos == start {
+            return Err(self.error(
+                format!("Expected a value for custom flag `{name}`"),
+                Span::new(start - 1, start),
+            ));
+        }
+        Ok(self.src[start..self.pos].to_string())
+    }
+
+    /// Find the `|` that starts the filters of the segment ending at `end`, if any.
+    ///
+    /// A `|` right after the `:` is the fill character if it's followed by an alignment,
+    /// and a `|` within quotes is part of the value of a custom flag (`@sep=" | "`).
+    fn find_pipe(&self, end: usize) -> Option<usize> {
+        let mut from = self.pos;
+        let mut rest = self.src[from..end].chars();
+        if rest.next() == Some(':') {
+            from += 1;
+            if let (Some(fill), Some(align)) = (rest.next(), rest.next()) {
+                if Alignment::from_char(align).is_some() {
+                    from += fill.len_utf8();
+                }
+            }
+        }
+        let mut quoted = false;
+        self.src[from..end]
+            .char_indices()
+            .find(|&(_, ch)| {
+                if ch == '"' {
+                    quoted = !quoted;
+                }
+                ch == '|' && !quoted
+            })
+            .map(|(idx, _)| from + idx)
+    }
+
+    /// Parse the filters of a segment, starting at the first `|`.
+    fn parse_filters(&mut self, pipe: usize, end: usize) -> Result<Vec<FilterCall>, ParseError> {
+        self.pos = pipe;
+        let mut filters = Vec::new();
+
+        while self.pos < end {
+            // Skip the `|`, which is always there after the previous filter
+            self.bump();
+            self.skip_whitespace(end);
+
+            let start = self.pos;
+            if !self.parse_identifier(end) {
+                return Err(self.error("Expected a filter name after `|`", Span::new(start, start)));
+            }
+            let name = self.src[start..self.pos].to_string();
+
+            let mut arguments = Vec::new();
+            self.skip_whitespace(end);
+            if self.peek().filter(|_| self.pos < end) == Some(':') {
+                self.bump();
+                loop {
+                    arguments.push(self.parse_filter_argument(end)?);
+                    if self.peek().filter(|_| self.pos < end) != Some(',') {
+                        break;
+                    }
+                    self.bump();
+                }
+            }
+
+            let span = Span::new(start, start + self.src[start..self.pos].trim_end().len());
+            match self.peek().filter(|_| self.pos < end) {
+                None | Some('|') => {}
+                Some(ch) => {
+                    return Err(self.error(
+                        format!("Expected `|` or `}}` after filter, found `{ch}`"),
+                        Span::new(self.pos, self.pos + ch.len_utf8()),
+                    ))
+                }
+            }
+
+            filters.push(FilterCall {
+                name,
+                arguments,
+                span,
+            });
+        }
+
+        Ok(filters)
+    }
+
+    /// Parse a single filter argument, which is either quoted (`"a, b"`) or ends at a `,` or `|`.
+    fn parse_filter_argument(&mut self, end: usize) -> Result<String, ParseError> {
+        self.skip_whitespace(end);
+        let start = self.pos;
+
+        let argument = if self.peek() == Some('"') {
+            let quoted = &self.src[start + 1..end];
+            let len = quoted.find('"').ok_or_else(|| {
+                self.error(
+                    "Expected `\"` to close filter argument",
+                    Span::new(start, end),
+                )
+            })?;
+            self.pos = start + len + 2;
+            quoted[..len].to_string()
+        } else {
+            let len = self.src[start..end].find([',', '|']).unwrap_or(end - start);
+            self.pos = start + len;
+            self.src[start..self.pos].trim_end().to_string()
+        };
+
+        self.skip_whitespace(end);
+        Ok(argument)
+    }
+
+    fn next_implicit_index(&mut self) -> String {
+        let index = self.next_implicit;
+        self.next_implicit += 1;
+        index.to_string()
+    }
+
+    /// Parse the key of a segment, or `None` for an implicit positional argument.
+    ///
+    /// Keys are argument indexes, identifiers separated by dots (`item.name`),
+    /// which can also contain indexes after the first identifier (`items.0.name`),
+    /// or loop variables (`@index`).
+    fn parse_key(&mut self, end: usize) -> Result<Option<String>, ParseError> {
+        let start = self.pos;
+        match self.peek().filter(|_| self.pos < end) {
+            None | Some(':' | '|') => return Ok(None),
+            Some(ch) if ch.is_ascii_digit() => {
+                let index = self.parse_count(end, "argument index")?;
+                return Ok(Some(index.unwrap_or_default().to_string()));
+            }
+            Some('@') => {
+                self.bump();
+                self.parse_identifier(end);
+                let variable = &self.src[start..self.pos];
+                if !LOOP_VARIABLES.contains(&variable) {
+                    return Err(self.error(
+                        format!("Unknown variable `{variable}`"),
+                        Span::new(start, self.pos),
+                    ));
+                }
+                return Ok(Some(variable.to_string()));
+            }
+            _ => {}
+        }
+
+        while self.parse_identifier(end) || (self.pos > start && self.parse_index(end)) {
+            let mut rest = self.src[self.pos..end].chars();
+            let is_path = rest.next() == Some('.')
+                && rest
+                    .next()
+                    .is_some_and(|ch| ch == '_' || ch.is_alphanumeric());
+            if !is_path {
+                break;
+            }
+            self.bump();
+        }
+
+        if self.pos == start {
+            let found = self
+                .peek()
+                .filter(|_| self.pos < end)
+                .map_or_else(|| String::from("end of segment"), |ch| format!("`{ch}`"));
+            return Err(self.error(
+                format!("Expected identifier or argument index, found {found}"),
+                Span::new(start, start),
+            ));
+        }
+
+        Ok(Some(self.src[start..self.pos].to_string()))
+    }
+
+    /// Parse a single identifier, returning whether there was one.
+    fn parse_identifier(&mut self, end: usize) -> bool {
+        let start = self.pos;
+        while let Some(ch) = self.peek().filter(|_| self.pos < end) {
+            let valid = if self.pos == start {
+                ch == '_' || ch.is_alphabetic()
+            } else {
+                ch == '_' || ch.is_alphanumeric()
+            };
+            if !valid {
+                break;
+            }
+            self.bump();
+        }
+        self.pos > start
+    }
+
+    /// Parse the digits of an index within a path, returning whether there were any.
+    fn parse_index(&mut self, end: usize) -> bool {
+        let start = self.pos;
+        while self
+            .peek()
+            .filter(|_| self.pos < end)
+            .is_some_and(|ch| ch.is_ascii_digit())
+        {
+            self.bump();
+        }
+        self.pos > start
+    }
+
+    /// Parse the standard flags of a segment, leaving a custom pattern like `1/0` or `+/-` intact.
+    ///
+    /// Standard flags are never followed by a `/`, so if they are, their last characters are
+    /// the start of the pattern instead, and the flags are parsed again without them.
+    fn parse_standard_flags(
+        &mut self,
+        flags: &mut CurlyFlags,
+        end: usize,
+    ) -> Result<(), ParseError> {
+        let (start, next_implicit) = (self.pos, self.next_implicit);
+        let mut flags_end = end;
+        loop {
+            *flags = CurlyFlags::default();
+            self.parse_flags(flags, flags_end)?;
+            if self.pos == start || self.peek().filter(|_| self.pos < end) != Some('/') {
+                return Ok(());
+            }
+
+            flags_end = self.src[..self.pos]
+                .char_indices()
+                .next_back()
+                .map_or(start, |(idx, _)| idx);
+            self.pos = start;
+            self.next_implicit = next_implicit;
+        }
+    }
+
+    /// Parse the standard flags of a segment, in the same order as [`format!`](std::format!):
+    /// `[[fill]align][sign]['#']['0'][width][grouping]['.' precision][type]`
+    ///
+    /// The width and precision can also be taken from an argument with `name$` or `N$`,
+    /// and the precision from the next positional argument with `.*`.
+    fn parse_flags(&mut self, flags: &mut CurlyFlags, end: usize) -> Result<(), ParseError> {
+        let next = |parser: &Self, n: usize| {
+            parser
+                .src
+                .get(parser.pos..end)
+                .and_then(|rest| rest.chars().nth(n))
+        };
+
+        if let Some(align) = next(self, 1).and_then(Alignment::from_char) {
+            flags.fill = self.bump().unwrap_or(' ');
+            flags.align = Some(align);
+            self.bump();
+        } else if let Some(align) = next(self, 0).and_then(Alignment::from_char) {
+            flags.align = Some(align);
+            self.bump();
+        }
+
+        match next(self, 0) {
+            Some('+') => {
+                flags.sign = Some(Sign::Plus);
+                self.bump();
+            }
+            Some('-') => {
+                flags.sign = Some(Sign::Minus);
+                self.bump();
+            }
+            _ => {}
+        }
+
+        if next(self, 0) == Some('#') {
+            flags.alternate = true;
+            self.bump();
+        }
+
+        if next(self, 0) == Some('0') && next(self, 1) != Some('$') {
+            flags.sign_aware_zero_pad = true;
+            self.bump();
+        }
+
+        flags.width_argument = self.parse_count_argument(end);
+        if flags.width_argument.is_none() {
+            flags.width = self.parse_count(end, "width")?;
+        }
+
+        if let Some(grouping) = next(self, 0).and_then(Grouping::from_char) {
+            flags.grouping = Some(grouping);
+            self.bump();
+        }
+
+        if next(self, 0) == Some('.') {
+            let dot = self.pos;
+            self.bump();
+            if next(self, 0) == Some('*') {
+                self.bump();
+                flags.precision_argument = Some(self.next_implicit_index());
+            } else {
+                flags.precision_argument = self.parse_count_argument(end);
+                if flags.precision_argument.is_none() {
+                    flags.precision = self.parse_count(end, "precision")?;
+                    if flags.precision.is_none() {
+                        return Err(
+                            self.error("Expected precision after `.`", Span::new(dot, self.pos))
+                        );
+                    }
+                }
+            }
+        }
+
+        if let Some(number_type) = next(self, 0).and_then(NumberType::from_char) {
+            if self.pos + 1 == end {
+                flags.number_type = number_type;
+                self.bump();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Parse an argument reference for a width or precision (`name$` or `N$`).
+    ///
+    /// Nothing is consumed if there isn't a `$` after the key, as it may be a plain
+    /// width or the start of the custom flags.
+    fn parse_count_argument(&mut self, end: usize) -> Option<String> {
+        let rest = &self.src[self.pos..end];
+        let len = rest
+            .find(|ch: char| ch != '_' && !ch.is_alphanumeric())
+            .unwrap_or(rest.len());
+        let (key, after) = rest.split_at(len);
+
+        let first = key.chars().next()?;
+        if !after.starts_with('$') {
+            return None;
+        }
+
+        let key = if first.is_ascii_digit() {
+            key.parse::<usize>().ok()?.to_string()
+        } else if first == '_' || first.is_alphabetic() {
+            key.to_string()
+        } else {
+            return None;
+        };
+
+        self.pos += len + 1;
+        Some(key)
+    }
+
+    fn parse_count(&mut self, end: usize, what: &str) -> Result<Option<usize>, ParseError> {
+        let start = self.pos;
+        while self.pos < end && self.peek().is_some_and(|ch| ch.is_ascii_digit()) {
+            self.bump();
+        }
+
+        if start == self.pos {
+            return Ok(None);
+        }
+
+        self.src[start..self.pos]
+            .parse()
+            .map(Some)
+            .map_err(|_| self.error(format!("Invalid {what}"), Span::new(start, self.pos)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placeholder(segment: &Segment) -> &Placeholder {
+        if let Segment::Placeholder(placeholder) = segment {
+            placeholder
+        } else {
+            panic!("Expected a placeholder, found {:?}", segment);
+        }
+    }
+
+    #[test]
+    fn parse_literals_and_escapes() {
+        let segments = parse("a {{b}} c").unwrap();
+        assert_eq!(segments, vec![Segment::Literal(String::from("a {b} c"))]);
+    }
+
+    #[test]
+    fn parse_placeholders() {
+        let segments = parse("Hello, {name}! {count:>5}").unwrap();
+        assert_eq!(segments.len(), 4);
+        assert_eq!(segments[0], Segment::Literal(String::from("Hello, ")));
+        assert_eq!(placeholder(&segments[1]).key, "name");
+        assert_eq!(placeholder(&segments[1]).span, Span::new(7, 13));
+        assert_eq!(placeholder(&segments[3]).flags.width, Some(5));
+        assert_eq!(
+            placeholder(&segments[3]).flags.align,
+            Some(Alignment::Right)
+        );
+    }
+
+    #[test]
+    fn parse_all_flags() {
+        let placeholder = parse_segment("value:*^+#012.3x", 1, 1).unwrap();
+        assert_eq!(
+            placeholder.flags,
+            CurlyFlags {
+                fill: '*',
+                align: Some(Alignment::Center),
+                width: Some(12),
+                precision: Some(3),
+                sign: Some(Sign::Plus),
+                sign_aware_zero_pad: true,
+                alternate: true,
+                number_type: NumberType::LowerHex,
+                ..CurlyFlags::default()
+            }
+        );
+        assert!(placeholder.custom_flags.is_none());
+
+        let placeholder = parse_segment("value:>12,.2", 1, 1).unwrap();
+        assert_eq!(placeholder.flags.grouping, Some(Grouping::Locale));
+        assert_eq!(placeholder.flags.precision, Some(2));
+        let placeholder = parse_segment("value:_x", 1, 1).unwrap();
+        assert_eq!(placeholder.flags.grouping, Some(Grouping::Underscore));
+        assert_eq!(placeholder.flags.number_type, NumberType::LowerHex);
+    }
+
+    #[test]
+    fn parse_custom_flags() {
+        let placeholder = parse_segment("date:%Y-%m-%d", 1, 1).unwrap();
+        assert_eq!(placeholder.flags, CurlyFlags::default());
+        assert_eq!(placeholder.custom_flags.as_deref(), Some("%Y-%m-%d"));
+
+        let placeholder = parse_segment("size:>8iec", 1, 1).unwrap();
+        assert_eq!(placeholder.flags.width, Some(8));
+        assert_eq!(placeholder.custom_flags.as_deref(), Some("iec"));
+
+        for pattern in ["1/0", "+/-", "0/1", "#/."] {
+            let placeholder = parse_segment(&format!("flag:{pattern}"), 1, 1).unwrap();
+            assert_eq!(placeholder.flags, CurlyFlags::default());
+            assert_eq!(placeholder.custom_flags.as_deref(), Some(pattern));
+        }
+        let placeholder = parse_segment("flag:>21/0", 1, 1).unwrap();
+        assert_eq!(placeholder.flags.width, Some(2));
+        assert_eq!(placeholder.custom_flags.as_deref(), Some("1/0"));
+    }
+
+    #[test]
+    fn error_unmatched_close() {
+        let err = parse("abc }").unwrap_err();
+        assert_eq!(err.span(), Span::new(4, 5));
+        assert_eq!(
+            err.to_string(),
+            "Unmatched `}` (use `}}` for a literal `}`) at 1:5"
+        );
+    }
+
+    #[test]
+    fn error_unterminated() {
+        let err = parse("line\n  {abc").unwrap_err();
+        assert_eq!((err.row(), err.col()), (2, 3));
+    }
+
+    #[test]
+    fn parse_positional() {
+        let segments = parse("{} {name} {:>3} {0} {07}").unwrap();
+        let keys: Vec<_> = segments
+            .iter()
+            .filter_map(|segment| match segment {
+                Segment::Placeholder(placeholder) => Some(placeholder.key.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(keys, vec!["0", "name", "1", "0", "7"]);
+    }
+
+    #[test]
+    fn parse_count_arguments() {
+        let placeholder = parse_segment("x:>width$.1$", 1, 1).unwrap();
+        assert_eq!(placeholder.flags.width, None);
+        assert_eq!(placeholder.flags.width_argument.as_deref(), Some("width"));
+        assert_eq!(placeholder.flags.precision_argument.as_deref(), Some("1"));
+
+        let placeholder = parse_segment("x:0$", 1, 1).unwrap();
+        assert!(!placeholder.flags.sign_aware_zero_pad);
+        assert_eq!(placeholder.flags.width_argument.as_deref(), Some("0"));
+
+        let placeholder = parse_segment("x:>8human", 1, 1).unwrap();
+        assert_eq!(placeholder.flags.width_argument, None);
+        assert_eq!(placeholder.custom_flags.as_deref(), Some("human"));
+    }
+
+    #[test]
+    fn parse_implicit_precision() {
+        let segments = parse("{:.*} {} {x:.*}").unwrap();
+        let first = placeholder(&segments[0]);
+        assert_eq!(first.flags.precision_argument.as_deref(), Some("0"));
+        assert_eq!(first.key, "1");
+        assert_eq!(placeholder(&segments[2]).key, "2");
+        let last = placeholder(&segments[4]);
+        assert_eq!(last.flags.precision_argument.as_deref(), Some("3"));
+        assert_eq!(last.key, "x");
+    }
+
+    #[test]
+    fn error_missing_key() {
+        let err = parse("{-x}").unwrap_err();
+        assert_eq!(
+            err.message(),
+            "Expected identifier or argument index, found `-`"
+        );
+    }
+
+    #[test]
+    fn error_missing_precision() {
+        let err = parse_segment("x:.", 3, 10).unwrap_err();
+        assert_eq!(err.to_string(), "Expected precision after `.` at 3:12");
+    }
+
+    #[test]
+    fn parse_conditionals() {
+        let segments = parse("a{#if x}b{#else if y}{c}{#else}d{/if}e").unwrap();
+        assert_eq!(segments.len(), 3);
+        let conditional = match &segments[1] {
+            Segment::Conditional(conditional) => conditional,
+            segment => panic!("Expected a conditional, found {:?}", segment),
+        };
+
+        assert_eq!(conditional.span, Span::new(1, 8));
+        assert_eq!(conditional.branches.len(), 2);
+        assert_eq!(conditional.branches[0].condition, "x");
+        assert_eq!(
+            conditional.branches[0].body,
+            vec![Segment::Literal(String::from("b"))]
+        );
+        assert_eq!(conditional.branches[1].condition, "y");
+        assert_eq!(placeholder(&conditional.branches[1].body[0]).key, "c");
+        assert_eq!(
+            conditional.otherwise,
+            Some(vec![Segment::Literal(String::from("d"))])
+        );
+        assert_eq!(keys(&segments), vec!["x", "y", "c"]);
+    }
+
+    #[test]
+    fn parse_nested_conditionals() {
+        let segments = parse("{#if a}{#if  b }{}{/if}{/if}{}").unwrap();
+        assert_eq!(keys(&segments), vec!["a", "b", "0", "1"]);
+    }
+
+    #[test]
+    fn error_unbalanced_blocks() {
+        let err = parse("a {/if}").unwrap_err();
+        assert_eq!(err.span(), Span::new(2, 7));
+        assert_eq!(
+            err.to_string(),
+            "Unbalanced `{/if}`: no matching `{#if}` at 1:3"
+        );
+
+        let err = parse("{#if a}\n{#if b}{/if}").unwrap_err();
+        assert_eq!(err.span(), Span::new(0, 7));
+        assert_eq!(err.message(), "Unclosed `{#if a}` block");
+
+        let err = parse("{#else}").unwrap_err();
+        assert_eq!(err.message(), "`{#else}` outside of an `{#if}` block");
+
+        let err = parse("{#if a}{#else}{#else if b}{/if}").unwrap_err();
+        assert_eq!(err.span(), Span::new(14, 26));
+        assert_eq!(err.message(), "Expected `{/if}`, found `{#else if b}`");
+    }
+
+    #[test]
+    fn error_invalid_tags() {
+        let err = parse("{#unless a}").unwrap_err();
+        assert_eq!(err.message(), "Unknown block `#unless a`");
+
+        let err = parse("{#if}{/if}").unwrap_err();
+        assert_eq!(err.message(), "Expected a key after `#if`");
+
+        let err = parse("{#if a b}{/if}").unwrap_err();
+        assert_eq!(err.message(), "Unexpected `b` in block tag");
+    }
+
+    #[test]
+    fn parse_loops() {
+        let segments = parse("{#each items as item}{@index}: {item.name}{#sep}, {/each}").unwrap();
+        let each = match &segments[0] {
+            Segment::Loop(each) => each,
+            segment => panic!("Expected a loop, found {:?}", segment),
+        };
+
+        assert_eq!(each.key, "items");
+        assert_eq!(each.binding.as_deref(), Some("item"));
+        assert_eq!(each.span, Span::new(0, 21));
+        assert_eq!(placeholder(&each.body[0]).key, "@index");
+        assert_eq!(placeholder(&each.body[2]).key, "item.name");
+        assert_eq!(
+            each.separator,
+            Some(vec![Segment::Literal(String::from(", "))])
+        );
+
+        let segments = parse("{#each rows}{#if @last}{total}{/if}{/each}").unwrap();
+        assert_eq!(keys(&segments), vec!["rows"]);
+    }
+
+    #[test]
+    fn error_invalid_loops() {
+        let err = parse("{#each items}{#else}{/each}").unwrap_err();
+        assert_eq!(err.message(), "Expected `{/each}`, found `{#else}`");
+
+        let err = parse("{#if a}{#sep}{/if}").unwrap_err();
+        assert_eq!(err.message(), "Expected `{/if}`, found `{#sep}`");
+
+        let err = parse("{#sep}").unwrap_err();
+        assert_eq!(err.message(), "`{#sep}` outside of an `{#each}` block");
+
+        let err = parse("{#each items for item}{/each}").unwrap_err();
+        assert_eq!(err.message(), "Expected `as` or `}`, found `for`");
+        assert_eq!(err.span(), Span::new(13, 16));
+
+        let err = parse("{#each items as}{/each}").unwrap_err();
+        assert_eq!(err.message(), "Expected a name after `as`");
+
+        let err = parse("{#each items}{@count}{/each}").unwrap_err();
+        assert_eq!(err.message(), "Unknown variable `@count`");
+
+        let err = parse("{#each items}").unwrap_err();
+        assert_eq!(err.message(), "Unclosed `{#each items}` block");
+    }
+
+    #[test]
+    fn parse_selects() {
+        let segments = parse(
+            "{n, plural, =0 {no files} one {# file} other {{#if big}many{/if} # files}} left",
+        )
+        .unwrap();
+        let select = match &segments[0] {
+            Segment::Select(select) => select,
+            segment => panic!("Expected a select, found {:?}", segment),
+        };
+
+        assert_eq!(select.key, "n");
+        assert_eq!(select.kind, SelectKind::Plural);
+        assert_eq!(select.span, Span::new(0, 74));
+        let selectors: Vec<_> = select
+            .cases
+            .iter()
+            .map(|case| case.selector.as_str())
+            .collect();
+        assert_eq!(selectors, vec!["=0", "one", "other"]);
+        assert_eq!(
+            select.cases[0].body,
+            vec![Segment::Literal(String::from("no files"))]
+        );
+        assert_eq!(placeholder(&select.cases[1].body[0]).key, "n");
+        assert_eq!(
+            placeholder(&select.cases[1].body[0]).span,
+            Span::new(31, 32)
+        );
+        assert_eq!(segments[1], Segment::Literal(String::from(" left")));
+        assert_eq!(keys(&segments), vec!["n", "n", "big", "n"]);
+
+        let segments = parse("{ role , select , admin {Admin #} other {{name}} }").unwrap();
+        let select = match &segments[0] {
+            Segment::Select(select) => select,
+            segment => panic!("Expected a select, found {:?}", segment),
+        };
+        assert_eq!(select.kind, SelectKind::Select);
+        assert_eq!(
+            select.cases[0].body,
+            vec![Segment::Literal(String::from("Admin #"))]
+        );
+        assert_eq!(keys(&segments), vec!["role", "name"]);
+    }
+
+    #[test]
+    fn error_nesting_too_deep() {
+        let nested = |depth: usize| "{#if a}".repeat(depth) + &"{/if}".repeat(depth);
+        assert!(parse(&nested(MAX_NESTING)).is_ok());
+        let err = parse(&nested(MAX_NESTING + 1)).unwrap_err();
+        assert_eq!(
+            err.message(),
+            "Sections are nested more than 64 levels deep"
+        );
+        assert_eq!(err.span(), Span::new(7 * MAX_NESTING, 7 * MAX_NESTING + 7));
+        assert!(parse(&nested(20_000)).is_err());
+
+        let selects = "{n, select, other {".repeat(20_000) + &"}}".repeat(20_000);
+        assert!(parse(&selects).is_err());
+        let loops = "{#each a}".repeat(20_000);
+        assert!(parse(&loops).is_err());
+    }
+
+    #[test]
+    fn error_invalid_selects() {
+        let err = parse("{n, count, other {}}").unwrap_err();
+        assert_eq!(
+            err.message(),
+            "Expected `plural` or `select` after `n,`, found `count`"
+        );
+
+        let err = parse("{n, plural, one {a}}").unwrap_err();
+        assert_eq!(err.message(), "Expected an `other` case in `{n, plural`");
+
+        let err = parse("{n, plural, several {a} other {b}}").unwrap_err();
+        assert_eq!(
+            err.message(),
+            "Expected a plural category or `=number` as a case of `n`, found `several`"
+        );
+
+        let err = parse("{n, plural, other b}").unwrap_err();
+        assert_eq!(err.message(), "Expected `{` after the case `other`");
+
+        let err = parse("{n, select, other {{#if a}}}").unwrap_err();
+        assert_eq!(err.message(), "Expected `{/if}`, found `}`");
+
+        let err = parse("{n, select, other {a").unwrap_err();
+        assert_eq!(err.message(), "Unclosed case `other`");
+        assert_eq!(err.span(), Span::new(12, 19));
+
+        let err = parse("{n, plural, other {a}").unwrap_err();
+        assert_eq!(err.message(), "Unclosed `{n, plural` section");
+    }
+
+    #[test]
+    fn parse_paths() {
+        let placeholder = parse_segment("order.total:>8", 1, 1).unwrap();
+        assert_eq!(placeholder.key, "order.total");
+        let placeholder = parse_segment("order.items.0.name", 1, 1).unwrap();
+        assert_eq!(placeholder.key, "order.items.0.name");
+        assert!(parse_segment("order.", 1, 1).is_err());
+        assert!(parse_segment("order.0x", 1, 1).is_err());
+    }
+
+    #[test]
+    fn parse_filters() {
+        let piped = parse_segment(
+            r#"name:>8 | upper | truncate: 20 , "..." |default:n/a"#,
+            1,
+            1,
+        )
+        .unwrap();
+        assert_eq!(piped.key, "name");
+        assert_eq!(piped.flags.width, Some(8));
+        assert!(piped.custom_flags.is_none());
+
+        let filters: Vec<_> = piped
+            .filters
+            .iter()
+            .map(|filter| (filter.name.as_str(), filter.arguments.clone()))
+            .collect();
+        assert_eq!(
+            filters,
+            vec![
+                ("upper", vec![]),
+                ("truncate", vec![String::from("20"), String::from("...")]),
+                ("default", vec![String::from("n/a")]),
+            ]
+        );
+        assert_eq!(piped.filters[0].span, Span::new(10, 15));
+
+        let filled = parse_segment("x:|^5|lower", 1, 1).unwrap();
+        assert_eq!(filled.flags.fill, '|');
+        assert_eq!(filled.filters[0].name, "lower");
+
+        let segments = parse("{|trim} {date:%Y|upper}").unwrap();
+        assert_eq!(placeholder(&segments[0]).key, "0");
+        assert_eq!(
+            placeholder(&segments[2]).custom_flags.as_deref(),
+            Some("%Y")
+        );
+
+        let quoted = parse_segment(r#"tags:@sep=" | " | upper"#, 1, 1).unwrap();
+        assert_eq!(quoted.custom_flags.as_deref(), Some(r#"@sep=" | ""#));
+        assert_eq!(quoted.filters[0].name, "upper");
+    }
+
+    #[test]
+    fn error_invalid_filters() {
+        let err = parse("{x | }").unwrap_err();
+        assert_eq!(err.message(), "Expected a filter name after `|`");
+
+        let err = parse("{x | replace:\"a,b}").unwrap_err();
+        assert_eq!(err.message(), "Expected `\"` to close filter argument");
+
+        let err = parse("{x | upper lower}").unwrap_err();
+        assert_eq!(err.message(), "Expected `|` or `}` after filter, found `l`");
+    }
+
+    #[test]
+    fn parse_named_custom_flags() {
+        let placeholder = parse_segment(r#"n:>8 @currency=EUR @short  @sep=", ""#, 1, 1).unwrap();
+        assert_eq!(placeholder.flags.width, Some(8));
+        assert_eq!(placeholder.custom.pattern, None);
+        assert_eq!(placeholder.custom.get("currency"), Some(Some("EUR")));
+        assert_eq!(placeholder.custom.get("short"), Some(None));
+        assert_eq!(placeholder.custom.get("sep"), Some(Some(", ")));
+        assert_eq!(placeholder.custom.get("long"), None);
+
+        let placeholder = parse_segment("date:%Y-%m-%d @tz=UTC", 1, 1).unwrap();
+        assert_eq!(placeholder.custom.pattern.as_deref(), Some("%Y-%m-%d"));
+        assert_eq!(placeholder.custom.get("tz"), Some(Some("UTC")));
+
+        let placeholder = parse_segment("email:user@example.com", 1, 1).unwrap();
+        assert_eq!(
+            placeholder.custom.pattern.as_deref(),
+            Some("user@example.com")
+        );
+        assert!(placeholder.custom.named.is_empty());
+    }
+
+    #[test]
+    fn error_invalid_custom_flags() {
+        let err = parse("{n:@}").unwrap_err();
+        assert_eq!(err.message(), "Expected a custom flag name after `@`");
+        assert_eq!(err.span(), Span::new(3, 4));
+
+        let err = parse("{n:@a=1 @a}").unwrap_err();
+        assert_eq!(err.message(), "Duplicate custom flag `a`");
+        assert_eq!(err.span(), Span::new(8, 10));
+
+        let err = parse("{n:@unit=}").unwrap_err();
+        assert_eq!(err.message(), "Expected a value for custom flag `unit`");
+
+        let err = parse("{n:@unit=\"kB}").unwrap_err();
+        assert_eq!(
+            err.message(),
+            "Expected `\"` to close the value of custom flag `unit`"
+        );
+
+        let err = parse("{n:@a.b}").unwrap_err();
+        assert_eq!(err.message(), "Unexpected `.` in custom flag `a`");
+        assert_eq!((err.row(), err.col()), (1, 6));
+    }
+}
@@ -16,5 +16,20 @@ struct SomeStruct {
 }
 
 fn main() {
-    println!("Hello, world!");
+    let some_struct = SomeStruct {
+        value1: String::from("Hello"),
+        internal_value_2: String::from("not available to format strings"),
+        rename_this_really_long_name_to_be_something_shorter: String::from("world"),
+        _automatically_ignored: String::new(),
+    };
+
+    println!("Internal value: {}", some_struct.internal_value_2);
+
+    let formatted = curly!(
+        "{value1}, {something_shorter}{punctuation}",
+        punctuation: String = String::from("!"),
+        ..some_struct: SomeStruct
+    );
+
+    println!("{}", formatted.unwrap());
 }